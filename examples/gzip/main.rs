@@ -9,7 +9,7 @@ fn main() -> Result<(), std::io::Error> {
 
     // Create a Gzip encoder
     let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-    encoder.write_all(&FILE_DATA)?;
+    encoder.write_all(FILE_DATA)?;
     let encoded_data = encoder.finish()?;
 
     // Write the compressed data to the output file
@@ -1,3 +1,5 @@
+#![allow(clippy::invisible_characters)]
+
 fn main() {
     let mut s = String::new();
     for line in FILE.lines() {
@@ -21,7 +23,7 @@ fn main() {
     println!("{s}");
 }
 
-const FILE: &'static str = r#"Character 	From Windows-1252 	From UTF-8
+const FILE: &str = r#"Character 	From Windows-1252 	From UTF-8
 space 	%20 	%20
 ! 	%21 	%21
 " 	%22 	%22
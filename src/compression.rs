@@ -0,0 +1,237 @@
+use crate::{
+    handler::Middleware,
+    my_http::{
+        AcceptEncoding, Body, ContentCoding, HeaderName, Method, Request, Response, StatusCode,
+    },
+};
+
+use color_eyre::Report;
+use std::borrow::Cow;
+
+/// [Middleware] that compresses response bodies with the best [ContentCoding] the request's
+/// `Accept-Encoding` allows, and marks every response it could have compressed with `Vary: Accept-Encoding`
+/// so caches keep the encoded and plain versions apart.
+///
+/// Bodies smaller than [Compression::min_size] or larger than [Compression::max_size], streamed bodies,
+/// responses that already have a `Content-Encoding` and media types in [Compression::skip_type] are left alone.
+/// A [Body::File] is read into memory to be compressed, which is what the size limit is for
+#[derive(Debug, Clone)]
+pub struct Compression {
+    min_size: usize,
+    max_size: usize,
+    skipped_types: Vec<Cow<'static, str>>,
+}
+impl Default for Compression {
+    fn default() -> Self {
+        return Compression {
+            min_size: Compression::DEFAULT_MIN_SIZE,
+            max_size: Compression::DEFAULT_MAX_SIZE,
+            skipped_types: Compression::ALREADY_COMPRESSED
+                .iter()
+                .map(|&media_type| Cow::from(media_type))
+                .collect(),
+        };
+    }
+}
+impl Compression {
+    /// Below this many bytes the coding overhead usually outweighs the savings
+    pub const DEFAULT_MIN_SIZE: usize = 256;
+    /// Larger bodies are sent as they are, a large [Body::File] keeps its zero-copy send
+    pub const DEFAULT_MAX_SIZE: usize = 4 * 1024 * 1024;
+    /// Media types that are compressed already and only grow when compressed again, skipped by default
+    pub const ALREADY_COMPRESSED: &'static [&'static str] = &[
+        "image/gif",
+        "image/jpeg",
+        "image/jpg",
+        "image/png",
+        "image/webp",
+        "image/avif",
+        "video/*",
+        "audio/*",
+        "font/woff",
+        "font/woff2",
+        "application/zip",
+        "application/gzip",
+        "application/x-gzip",
+        "application/zstd",
+        "application/x-bzip2",
+        "application/x-xz",
+        "application/x-7z-compressed",
+        "application/x-rar-compressed",
+    ];
+
+    pub fn new() -> Self {
+        return Compression::default();
+    }
+    /// Sets the smallest body, in bytes, that is compressed
+    pub fn min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        return self;
+    }
+
+    /// Sets the largest body, in bytes, that is compressed
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        return self;
+    }
+    /// Never compresses responses of `media_type`, either a full type like `image/png` or a whole
+    /// top level type like `video/*`
+    pub fn skip_type(mut self, media_type: impl Into<Cow<'static, str>>) -> Self {
+        self.skipped_types.push(media_type.into());
+        return self;
+    }
+    /// Compresses responses of `media_type` again after [Compression::skip_type] or [Compression::ALREADY_COMPRESSED]
+    pub fn compress_type(mut self, media_type: &str) -> Self {
+        self.skipped_types
+            .retain(|skipped| !skipped.eq_ignore_ascii_case(media_type));
+        return self;
+    }
+
+    pub(crate) fn is_skipped(&self, content_type: &str) -> bool {
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+        let top_level = essence.split('/').next().unwrap_or_default();
+        return self
+            .skipped_types
+            .iter()
+            .any(|skipped| match skipped.strip_suffix("/*") {
+                Some(skipped_top_level) => skipped_top_level.eq_ignore_ascii_case(top_level),
+                None => skipped.eq_ignore_ascii_case(essence),
+            });
+    }
+    fn is_compressible(&self, response: &Response) -> bool {
+        let status = response.status();
+        return status != StatusCode::NO_CONTENT
+            && status != StatusCode::NOT_MODIFIED
+            && status != StatusCode::PARTIAL_CONTENT
+            && !response.headers().contains(HeaderName::CONTENT_ENCODING)
+            && !response
+                .headers()
+                .content_type()
+                .is_some_and(|content_type| self.is_skipped(content_type))
+            && response
+                .body()
+                .len()
+                .is_some_and(|len| (self.min_size..=self.max_size).contains(&len));
+    }
+}
+impl Middleware for Compression {
+    fn after(&self, request: &Request, response: &mut Response) -> Result<(), Report> {
+        if !self.is_compressible(response) {
+            return Ok(());
+        }
+        if !response
+            .headers()
+            .contains_token(HeaderName::VARY, HeaderName::ACCEPT_ENCODING.as_str())
+        {
+            response.append_header(HeaderName::VARY, HeaderName::ACCEPT_ENCODING.as_str());
+        }
+        // the body of a HEAD response is dropped before it is sent, so encoding it would be wasted work.
+        // the plain representation's headers are still accurate for it
+        if request.method() == Method::Head {
+            return Ok(());
+        }
+
+        let accept_encoding =
+            AcceptEncoding::parse(request.headers().get_list(HeaderName::ACCEPT_ENCODING));
+        let coding = match accept_encoding.negotiate(ContentCoding::SUPPORTED) {
+            Some(ContentCoding::Identity) | None => return Ok(()),
+            Some(coding) => coding,
+        };
+
+        let encoded = response.body().encode(coding)?;
+        response.set_body(Body::Data(encoded));
+        response.set_header(HeaderName::CONTENT_ENCODING, coding.as_str());
+        // the encoded bytes differ from the ones a strong tag promised, so only weak comparison may match them
+        if let Some(etag) = response.headers().get(HeaderName::ETAG) {
+            if !etag.as_str().starts_with("W/") {
+                let weak_etag = format!("W/{}", etag);
+                response.set_header(HeaderName::ETAG, weak_etag);
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::handler::{Chain, Handler};
+
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_body_is_compressed_when_accepted() {
+        let text = "quack ".repeat(100);
+        let handler = Chain::new(move |_: &mut Request| Ok(Response::text(text.clone())))
+            .layer(Compression::new());
+
+        let mut request = "GET / HTTP/1.1\r\nAccept-Encoding: compress;q=1, gzip;q=0.8\r\n\r\n"
+            .parse()
+            .unwrap();
+        let response = handler.handle(&mut request).unwrap();
+        let headers = response.headers();
+        assert_eq!(
+            headers.get(HeaderName::CONTENT_ENCODING).unwrap().as_str(),
+            "gzip"
+        );
+        assert_eq!(
+            headers.get(HeaderName::VARY).unwrap().as_str(),
+            "Accept-Encoding"
+        );
+        assert_eq!(
+            headers.content_length(),
+            Some(response.body().as_ref().len() as u64)
+        );
+
+        let mut decoded = String::new();
+        GzDecoder::new(response.body().as_ref())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, "quack ".repeat(100));
+
+        let mut request = "GET / HTTP/1.1\r\nAccept-Encoding: gzip;q=0\r\n\r\n"
+            .parse()
+            .unwrap();
+        let response = handler.handle(&mut request).unwrap();
+        assert!(!response.headers().contains(HeaderName::CONTENT_ENCODING));
+        assert!(response.headers().contains(HeaderName::VARY));
+        assert_eq!(response.body().len(), Some(600));
+
+        let mut request = "HEAD / HTTP/1.1\r\nAccept-Encoding: gzip\r\n\r\n"
+            .parse()
+            .unwrap();
+        let response = handler.handle(&mut request).unwrap();
+        assert!(!response.headers().contains(HeaderName::CONTENT_ENCODING));
+        assert!(response.headers().contains(HeaderName::VARY));
+        assert_eq!(response.headers().content_length(), Some(600));
+    }
+
+    #[test]
+    fn test_compressed_types_are_skipped() {
+        let compression = Compression::new().skip_type("application/x-custom");
+        assert!(compression.is_skipped("image/gif"));
+        assert!(compression.is_skipped("Video/MP4"));
+        assert!(compression.is_skipped("application/x-custom; charset=utf-8"));
+        assert!(!compression.is_skipped("text/html; charset=utf-8"));
+        assert!(!compression.is_skipped("image/svg+xml"));
+
+        let handler = Chain::new(|_: &mut Request| {
+            Ok(Response::builder()
+                .header(HeaderName::CONTENT_TYPE, "image/jpeg")
+                .body(vec![0xFF; 1024]))
+        })
+        .layer(Compression::new());
+        let mut request = "GET / HTTP/1.1\r\nAccept-Encoding: gzip\r\n\r\n"
+            .parse()
+            .unwrap();
+        let response = handler.handle(&mut request).unwrap();
+        assert!(!response.headers().contains(HeaderName::CONTENT_ENCODING));
+        assert_eq!(response.body().len(), Some(1024));
+
+        assert!(!Compression::new()
+            .compress_type("image/png")
+            .is_skipped("image/png"));
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{
+    my_http::Request,
+    static_files::{StaticFiles, SymlinkPolicy},
+    thread_pool::ThreadPool,
+};
+
+use color_eyre::{eyre::eyre, Report};
+use std::{path::PathBuf, time::Duration};
+
+/// Runtime settings for the server binary
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub address: String,
+    pub worker_count: usize,
+    pub queue_depth: usize,
+    /// How long a persistent connection may sit idle waiting for the next request
+    pub keep_alive_timeout: Duration,
+    /// How many requests one connection may send before the server closes it
+    pub max_requests_per_connection: usize,
+    /// The largest request body the server will read, larger bodies are answered with `413 Payload Too Large`
+    pub max_body_size: usize,
+    /// The directory served at `/`
+    pub root: PathBuf,
+    /// More `(url prefix, directory)` pairs served next to [Config::root]
+    pub mounts: Vec<(String, PathBuf)>,
+    /// Extra `(extension, media type)` mappings for static files, over the built in ones
+    pub mime_types: Vec<(String, String)>,
+    /// Which symlinks under the serve directory may be followed
+    pub symlinks: SymlinkPolicy,
+    /// The file names a directory is served through, the first one that exists is used
+    pub index_files: Vec<String>,
+    /// Whether directories without an index file are listed
+    pub autoindex: bool,
+    /// Whether directory listings include dotfiles
+    pub show_hidden: bool,
+    /// Whether error pages show the full report of the error behind them
+    pub dev_mode: bool,
+}
+impl Default for Config {
+    fn default() -> Self {
+        return Config {
+            address: "localhost:8000".to_string(),
+            worker_count: ThreadPool::<()>::DEFAULT_WORKER_COUNT,
+            queue_depth: ThreadPool::<()>::DEFAULT_QUEUE_DEPTH,
+            keep_alive_timeout: Duration::from_secs(5),
+            max_requests_per_connection: 100,
+            max_body_size: Request::DEFAULT_MAX_BODY_SIZE,
+            root: PathBuf::from("./web"),
+            mounts: Vec::new(),
+            mime_types: Vec::new(),
+            symlinks: SymlinkPolicy::default(),
+            index_files: StaticFiles::DEFAULT_INDEX_FILES
+                .iter()
+                .map(|index_file| index_file.to_string())
+                .collect(),
+            autoindex: false,
+            show_hidden: false,
+            dev_mode: false,
+        };
+    }
+}
+impl Config {
+    /// Parses `[address] [--workers N] [--queue-depth N] [--keep-alive-timeout SECONDS] [--max-requests N] [--max-body-size BYTES]`
+    /// `[--root DIR] [--mount PREFIX=DIR]... [--mime EXTENSION=TYPE]... [--symlinks follow|within-root|deny] [--index-files NAME,...] [--autoindex] [--show-hidden] [--dev]` (without the program name)
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, Report> {
+        let mut config = Config::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--workers" => config.worker_count = Config::parse_value(&arg, args.next())?,
+                "--queue-depth" => config.queue_depth = Config::parse_value(&arg, args.next())?,
+                "--keep-alive-timeout" => {
+                    // a zero read timeout is an error to the socket, so every connection would fail
+                    let seconds = Config::parse_value(&arg, args.next())?;
+                    if seconds == 0 {
+                        Err(eyre!(
+                            "in Config::from_args {} has to be at least 1 second",
+                            arg
+                        ))?;
+                    }
+                    config.keep_alive_timeout = Duration::from_secs(seconds as u64)
+                }
+                "--max-requests" => {
+                    config.max_requests_per_connection = Config::parse_value(&arg, args.next())?
+                }
+                "--max-body-size" => config.max_body_size = Config::parse_value(&arg, args.next())?,
+                "--root" => {
+                    config.root = args
+                        .next()
+                        .ok_or(eyre!("{} is missing a value", arg))?
+                        .into()
+                }
+                "--mount" => {
+                    let mount = args.next().ok_or(eyre!("{} is missing a value", arg))?;
+                    let (prefix, directory) = mount
+                        .split_once('=')
+                        .filter(|(prefix, directory)| {
+                            prefix.starts_with('/') && !directory.is_empty()
+                        })
+                        .ok_or(eyre!(
+                            "{} is not a valid value for {}, expected PREFIX=DIR",
+                            mount,
+                            arg
+                        ))?;
+                    config
+                        .mounts
+                        .push((prefix.to_string(), PathBuf::from(directory)));
+                }
+                "--mime" => {
+                    let mapping = args.next().ok_or(eyre!("{} is missing a value", arg))?;
+                    let (extension, media_type) = mapping
+                        .split_once('=')
+                        .filter(|(extension, media_type)| {
+                            !extension.is_empty() && media_type.contains('/')
+                        })
+                        .ok_or(eyre!(
+                            "{} is not a valid value for {}, expected EXTENSION=TYPE",
+                            mapping,
+                            arg
+                        ))?;
+                    config
+                        .mime_types
+                        .push((extension.to_string(), media_type.to_string()));
+                }
+                "--symlinks" => {
+                    config.symlinks = args
+                        .next()
+                        .ok_or(eyre!("{} is missing a value", arg))?
+                        .parse()?
+                }
+                "--index-files" => {
+                    config.index_files = args
+                        .next()
+                        .ok_or(eyre!("{} is missing a value", arg))?
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|index_file| !index_file.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                }
+                "--autoindex" => config.autoindex = true,
+                "--show-hidden" => config.show_hidden = true,
+                "--dev" => config.dev_mode = true,
+                flag if flag.starts_with("--") => Err(eyre!("{} is not a valid option", flag))?,
+                _ => config.address = arg,
+            }
+        }
+
+        return Ok(config);
+    }
+
+    fn parse_value(flag: &str, value: Option<String>) -> Result<usize, Report> {
+        let value = value.ok_or(eyre!("{} is missing a value", flag))?;
+        return value.parse().map_err(|parse_error| {
+            eyre!(
+                "{} is not a valid value for {}\n{}",
+                value,
+                flag,
+                parse_error
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_keep_alive_timeout() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+        let config = Config::from_args(args(&["--keep-alive-timeout", "30"])).unwrap();
+        assert_eq!(config.keep_alive_timeout, Duration::from_secs(30));
+        assert!(Config::from_args(args(&["--keep-alive-timeout", "0"])).is_err());
+    }
+}
@@ -0,0 +1,322 @@
+use crate::{
+    error_response,
+    handler::Handler,
+    my_http::{
+        escape_html, escape_json, preferred_media_type, Body, HeaderName, Request, Response,
+        StatusCode,
+    },
+};
+
+use color_eyre::Report;
+use std::{
+    fs,
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Counts the request ids handed out by this process
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(0);
+
+/// A [Handler] that gives the error responses of another one a body, and turns the errors it returns into responses.
+///
+/// Error responses that come back with no body are filled in from a template in [ErrorPages::new]'s directory,
+/// the first of `404.html`, `40x.html` and `4xx.html` that exists for a 404. Without a template a plain built in
+/// page is used. Templates can hold the placeholders `{{status}}`, `{{reason}}`, `{{path}}`, `{{request_id}}`
+/// and `{{details}}`, which is the error report in [ErrorPages::dev_mode] and empty otherwise.
+///
+/// Clients whose `Accept` header prefers json get an RFC 9457 `application/problem+json` document instead.
+/// Every error response carries an `X-Request-Id`, the client's own if it sent one.
+/// The errors answered outside the handler, see [Handler::render_error], get the same pages.
+/// Kept inside any [crate::handler::Chain] so its layers see the finished page
+pub struct ErrorPages<H> {
+    handler: H,
+    templates: PathBuf,
+    dev_mode: bool,
+}
+impl<H: Handler> ErrorPages<H> {
+    /// Wraps `handler`, looking for error page templates in `templates`
+    pub fn new(handler: H, templates: impl Into<PathBuf>) -> Self {
+        return ErrorPages {
+            handler,
+            templates: templates.into(),
+            dev_mode: false,
+        };
+    }
+    /// Puts the full report of errors the handler returns into the pages. Only meant for development,
+    /// the report can show file paths and other internals to anyone who triggers an error
+    pub fn dev_mode(mut self, dev_mode: bool) -> Self {
+        self.dev_mode = dev_mode;
+        return self;
+    }
+
+    /// The template for `status`, most specific file name first
+    fn template(&self, status: StatusCode) -> Option<String> {
+        let code = status.as_u16().to_string();
+        return [
+            format!("{}.html", code),
+            format!("{}x.html", &code[..2]),
+            format!("{}xx.html", &code[..1]),
+        ]
+        .iter()
+        .find_map(|name| fs::read_to_string(self.templates.join(name)).ok());
+    }
+    /// Gives an error `response` that has no body a page, and an `X-Request-Id`
+    fn fill(&self, request: Option<&Request>, response: &mut Response, details: &str) {
+        let status = response.status();
+        let is_error = status.is_client_error() || status.is_server_error();
+        if !is_error || response.body().len() != Some(0) {
+            return;
+        }
+        let request_id = request
+            .and_then(|request| request.header(HeaderName::X_REQUEST_ID))
+            .map_or_else(new_request_id, |request_id| request_id.to_string());
+
+        self.render(request, response, &request_id, details);
+        response.set_header(HeaderName::X_REQUEST_ID, request_id);
+    }
+    fn render(
+        &self,
+        request: Option<&Request>,
+        response: &mut Response,
+        request_id: &str,
+        details: &str,
+    ) {
+        let status = response.status();
+        let path = request.map_or("", |request| {
+            request.path().split('?').next().unwrap_or_default()
+        });
+
+        let prefers_json = request.is_some_and(|request| {
+            preferred_media_type(
+                request,
+                &["text/html", "application/json", "application/problem+json"],
+            )
+            .is_some_and(|media_type| media_type != "text/html")
+        });
+        if prefers_json {
+            let detail = if details.is_empty() {
+                String::new()
+            } else {
+                format!(",\"detail\":\"{}\"", escape_json(details))
+            };
+            response.set_body(Body::from(format!(
+                "{{\"type\":\"about:blank\",\"title\":\"{}\",\"status\":{},\"instance\":\"{}\",\"request_id\":\"{}\"{}}}",
+                escape_json(status.reason()),
+                status.as_u16(),
+                escape_json(path),
+                escape_json(request_id),
+                detail
+            )));
+            response.set_header(HeaderName::CONTENT_TYPE, "application/problem+json");
+            return;
+        }
+
+        let template = self
+            .template(status)
+            .unwrap_or(DEFAULT_TEMPLATE.to_string());
+        response.set_body(Body::from(fill_placeholders(
+            &template,
+            &[
+                ("status", status.as_u16().to_string()),
+                ("reason", escape_html(status.reason())),
+                ("path", escape_html(path)),
+                ("request_id", escape_html(request_id)),
+                ("details", escape_html(details)),
+            ],
+        )));
+        response.set_header(HeaderName::CONTENT_TYPE, "text/html; charset=utf-8");
+    }
+}
+impl<H: Handler> Handler for ErrorPages<H> {
+    fn handle(&self, request: &mut Request) -> Result<Response, Report> {
+        let (mut response, details) = match self.handler.handle(request) {
+            Ok(response) => (response, String::new()),
+            Err(error) => {
+                let details = if self.dev_mode {
+                    strip_ansi(&format!("{:?}", error))
+                } else {
+                    String::new()
+                };
+                (error_response(&error), details)
+            }
+        };
+
+        self.fill(Some(request), &mut response, &details);
+        return Ok(response);
+    }
+    fn render_error(&self, request: Option<&Request>, status: StatusCode) -> Response {
+        let mut response = Response::empty(status);
+        self.fill(request, &mut response, "");
+        return response;
+    }
+}
+
+const DEFAULT_TEMPLATE: &str = "<!DOCTYPE html>
+<html>
+<head><meta charset=\"utf-8\"><title>{{status}} {{reason}}</title></head>
+<body>
+<h1>{{status}} {{reason}}</h1>
+<p>{{path}}</p>
+<pre>{{details}}</pre>
+<hr><small>request id {{request_id}}</small>
+</body>
+</html>
+";
+
+/// Replaces each `{{name}}` in `template` with its value in `placeholders`, in one pass
+/// so a value that itself contains `{{name}}` is left as it is
+fn fill_placeholders(template: &str, placeholders: &[(&str, String)]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        filled.push_str(&rest[..start]);
+        let after_open = &rest[start + 2..];
+        let placeholder = after_open.find("}}").and_then(|end| {
+            let name = &after_open[..end];
+            return placeholders
+                .iter()
+                .find(|(placeholder, _)| *placeholder == name)
+                .map(|(_, value)| (end, value));
+        });
+        match placeholder {
+            Some((end, value)) => {
+                filled.push_str(value);
+                rest = &after_open[end + 2..];
+            }
+            None => {
+                filled.push_str("{{");
+                rest = after_open;
+            }
+        }
+    }
+    filled.push_str(rest);
+    return filled;
+}
+
+/// An id unique within this process that is unlikely to repeat across restarts
+fn new_request_id() -> String {
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or_default();
+    return format!(
+        "{:x}-{:x}",
+        started,
+        NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+    );
+}
+
+/// Removes the terminal color codes `color_eyre` puts in its reports
+fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut characters = text.chars();
+    while let Some(character) = characters.next() {
+        if character == '\x1B' {
+            // skips `[`, the parameters and the final letter of the escape sequence
+            for character in characters.by_ref() {
+                if character.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        stripped.push(character);
+    }
+    return stripped;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::TempDir;
+    use color_eyre::eyre::eyre;
+
+    fn handler(request: &mut Request) -> Result<Response, Report> {
+        return match request.path() {
+            "/error" => Err(eyre!("the \x1B[31mdatabase\x1B[0m is on fire")),
+            "/teapot" => Ok(Response::builder()
+                .status(StatusCode::IM_A_TEAPOT)
+                .body("short and stout")),
+            _ => Ok(Response::empty(StatusCode::NOT_FOUND)),
+        };
+    }
+
+    fn get(error_pages: &ErrorPages<impl Handler>, request: &str) -> Response {
+        let mut request: Request = request.parse().unwrap();
+        return error_pages.handle(&mut request).unwrap();
+    }
+
+    #[test]
+    fn test_error_pages() {
+        let temp_dir = TempDir::new("error-pages");
+        let templates = temp_dir.path();
+        fs::write(
+            templates.join("4xx.html"),
+            "<p>{{status}} {{reason}} at {{path}} ({{request_id}})</p>",
+        )
+        .unwrap();
+        let error_pages = ErrorPages::new(handler, templates);
+
+        let response = get(
+            &error_pages,
+            "GET /missing<b>?q=1 HTTP/1.1\r\nX-Request-Id: abc\r\n\r\n",
+        );
+        assert_eq!(
+            response.body().as_ref(),
+            b"<p>404 Not Found at /missing&lt;b&gt; (abc)</p>"
+        );
+
+        let response = get(&error_pages, "GET /teapot HTTP/1.1\r\n\r\n");
+        assert_eq!(response.body().as_ref(), b"short and stout");
+
+        let response = get(&error_pages, "GET /error HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let page = String::from_utf8_lossy(response.body().as_ref()).to_string();
+        assert!(page.contains("<h1>500 Internal Server Error</h1>"));
+        assert!(!page.contains("on fire"));
+
+        let request: Request = "GET /bad HTTP/1.1\r\n\r\n".parse().unwrap();
+        let response = error_pages.render_error(Some(&request), StatusCode::BAD_REQUEST);
+        assert!(response
+            .body()
+            .as_cow_str()
+            .starts_with("<p>400 Bad Request at /bad ("));
+        let response = error_pages.render_error(None, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response
+            .body()
+            .as_cow_str()
+            .contains("<h1>503 Service Unavailable</h1>"));
+        assert!(response.headers().contains(HeaderName::X_REQUEST_ID));
+
+        let error_pages = error_pages.dev_mode(true);
+        let response = get(
+            &error_pages,
+            "GET /error HTTP/1.1\r\nAccept: application/json\r\n\r\n",
+        );
+        assert_eq!(
+            response
+                .headers()
+                .get(HeaderName::CONTENT_TYPE)
+                .map(|value| value.as_str()),
+            Some("application/problem+json")
+        );
+        let problem = String::from_utf8_lossy(response.body().as_ref()).to_string();
+        assert!(problem.starts_with(
+            "{\"type\":\"about:blank\",\"title\":\"Internal Server Error\",\"status\":500,\"instance\":\"/error\",\"request_id\":\""
+        ));
+        assert!(problem.contains("the database is on fire"));
+    }
+
+    #[test]
+    fn test_placeholders_are_filled_once() {
+        let filled = fill_placeholders(
+            "{{path}} {{details}} {{unknown}} {{",
+            &[
+                ("path", "/{{details}}".to_string()),
+                ("details", "on fire".to_string()),
+            ],
+        );
+        assert_eq!(filled, "/{{details}} on fire {{unknown}} {{");
+    }
+}
@@ -0,0 +1,167 @@
+use crate::my_http::{Request, Response, StatusCode};
+
+use color_eyre::{owo_colors::OwoColorize, Report};
+
+/// Answers a [Request] with a [Response].
+///
+/// Implemented for closures and functions taking `&mut Request`, for [crate::router::Router],
+/// [crate::static_files::StaticFiles] and for a [Chain] of middleware around another handler
+pub trait Handler: Send + Sync {
+    fn handle(&self, request: &mut Request) -> Result<Response, Report>;
+    /// The response for an error [Handler::handle] never got to answer: a request that could not be parsed,
+    /// a panic or a connection the server has no room for. `request` is [None] when there is none to go by.
+    /// An empty response with `status` unless a handler like [crate::error_pages::ErrorPages] fills it in
+    fn render_error(&self, request: Option<&Request>, status: StatusCode) -> Response {
+        let _ = request;
+        return Response::empty(status);
+    }
+}
+impl<F> Handler for F
+where
+    F: Fn(&mut Request) -> Result<Response, Report> + Send + Sync,
+{
+    fn handle(&self, request: &mut Request) -> Result<Response, Report> {
+        return self(request);
+    }
+}
+
+/// A layer that runs around a [Handler] as part of a [Chain]
+pub trait Middleware: Send + Sync {
+    /// Runs before the handler. Returning a response skips the handler and every layer after this one
+    fn before(&self, request: &mut Request) -> Result<Option<Response>, Report> {
+        let _ = request;
+        return Ok(None);
+    }
+    /// Runs after the handler, or after a later layer answered in [Middleware::before]
+    fn after(&self, request: &Request, response: &mut Response) -> Result<(), Report> {
+        let _ = (request, response);
+        return Ok(());
+    }
+}
+
+/// A [Handler] wrapped in layers of [Middleware].
+///
+/// `before` hooks run in the order the layers were added and `after` hooks in reverse,
+/// so the first layer added is the outermost one.
+/// When a layer answers in `before` only the layers outside it see the response in `after`
+///
+/// # Example
+/// ```rust
+/// # use scratchserver::{handler::{Chain, Logger}, my_http::{Request, Response}};
+/// let handler = Chain::new(|_: &mut Request| Ok(Response::text("hello"))).layer(Logger);
+/// ```
+pub struct Chain<H> {
+    handler: H,
+    layers: Vec<Box<dyn Middleware>>,
+}
+impl<H: Handler> Chain<H> {
+    pub fn new(handler: H) -> Self {
+        return Chain {
+            handler,
+            layers: Vec::new(),
+        };
+    }
+    /// Adds a layer inside the ones already added
+    pub fn layer(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.layers.push(Box::new(middleware));
+        return self;
+    }
+}
+impl<H: Handler> Handler for Chain<H> {
+    fn handle(&self, request: &mut Request) -> Result<Response, Report> {
+        let mut entered = 0;
+        let mut short_circuit = None;
+        for layer in self.layers.iter() {
+            if let Some(response) = layer.before(request)? {
+                short_circuit = Some(response);
+                break;
+            }
+            entered += 1;
+        }
+
+        let mut response = match short_circuit {
+            Some(response) => response,
+            None => self.handler.handle(request)?,
+        };
+
+        for layer in self.layers[..entered].iter().rev() {
+            layer.after(request, &mut response)?;
+        }
+
+        return Ok(response);
+    }
+    fn render_error(&self, request: Option<&Request>, status: StatusCode) -> Response {
+        return self.handler.render_error(request, status);
+    }
+}
+
+/// Prints every request and the response it got
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Logger;
+impl Middleware for Logger {
+    fn before(&self, request: &mut Request) -> Result<Option<Response>, Report> {
+        // the body can be large or hold credentials, so only its length is printed
+        let summary = format!(
+            "{} {}\n{}body: {} bytes\n",
+            request.method(),
+            request.path(),
+            request.headers(),
+            request.body().len()
+        );
+        println!("{}", summary.bright_blue());
+        return Ok(None);
+    }
+    fn after(&self, _request: &Request, response: &mut Response) -> Result<(), Report> {
+        println!("Response:\n{}", response);
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::my_http::{HeaderName, StatusCode};
+
+    /// Appends its name to an `X-Trace` header on the way out, answering early with a 401 when its flag is set
+    struct Trace(&'static str, bool);
+    impl Middleware for Trace {
+        fn before(&self, _request: &mut Request) -> Result<Option<Response>, Report> {
+            return Ok(self.1.then(|| Response::empty(StatusCode::UNAUTHORIZED)));
+        }
+        fn after(&self, _request: &Request, response: &mut Response) -> Result<(), Report> {
+            response.append_header("X-Trace", self.0);
+            return Ok(());
+        }
+    }
+
+    fn traces(response: &Response) -> Vec<&str> {
+        return response
+            .headers()
+            .get_all("X-Trace")
+            .map(|value| value.as_str())
+            .collect();
+    }
+
+    #[test]
+    fn test_layers_wrap_the_handler() {
+        let handler = |_: &mut Request| Ok(Response::text("handled"));
+
+        let chain = Chain::new(handler)
+            .layer(Trace("outer", false))
+            .layer(Trace("inner", false));
+        let mut request = "GET / HTTP/1.1\r\n\r\n".parse().unwrap();
+        let response = chain.handle(&mut request).unwrap();
+        assert_eq!(response.body().as_ref(), b"handled");
+        assert_eq!(traces(&response), ["inner", "outer"]);
+
+        let chain = Chain::new(handler)
+            .layer(Trace("outer", false))
+            .layer(Trace("auth", true))
+            .layer(Trace("inner", false));
+        let mut request = "GET / HTTP/1.1\r\n\r\n".parse().unwrap();
+        let response = chain.handle(&mut request).unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(traces(&response), ["outer"]);
+        assert!(!response.headers().contains(HeaderName::CONTENT_TYPE));
+    }
+}
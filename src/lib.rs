@@ -1,43 +1,172 @@
+#![allow(clippy::needless_return)]
+
+pub mod compression;
+pub mod config;
+pub mod error_pages;
+pub mod handler;
 pub mod my_http;
-use crate::my_http::{Request, Response};
+pub mod router;
+pub mod static_files;
+#[cfg(test)]
+mod test_util;
+pub mod thread_pool;
+
+use crate::{
+    config::Config,
+    handler::Handler,
+    my_http::{HeaderName, HttpError, Method, Request, Response, StatusCode},
+};
 
 use color_eyre::{owo_colors::OwoColorize, Report};
 use std::{
-    io::{BufReader, BufWriter, Write},
+    io::{BufReader, BufWriter, ErrorKind, Write},
     net::TcpStream,
+    panic::{self, AssertUnwindSafe},
 };
 
-/// This assumes that the `web` directory lives in the root of the crate. and `cargo run` must be ran from the crate root
-pub const SERVE_DIR: &'static str = "./web";
-
+/// Serves requests from one connection with `handler` until the client asks to close it, goes idle for
+/// [Config::keep_alive_timeout], or reaches [Config::max_requests_per_connection]
 pub fn handle_connection(
-    (reader, mut writer): (BufReader<TcpStream>, BufWriter<TcpStream>),
+    (mut reader, mut writer): (BufReader<TcpStream>, BufWriter<TcpStream>),
+    config: &Config,
+    handler: &dyn Handler,
 ) -> Result<(), Report> {
+    let peer_addr = reader.get_ref().peer_addr().ok();
+    let local_addr = reader.get_ref().local_addr().ok();
     println!(
         "{}\n{}{:?}",
         "Connection established".green(),
         "Peer Address: ".green(),
-        reader.get_ref().peer_addr().green()
+        peer_addr.green()
     );
 
-    let request = Request::try_from(reader)?;
-    println!("{:#?}\n", request.bright_blue());
+    reader
+        .get_ref()
+        .set_read_timeout(Some(config.keep_alive_timeout))?;
 
-    let response = Response::try_from(request)?;
-    println!("Response:\n{}", response);
+    for requests_served in 1.. {
+        let mut request = match Request::read_from(&mut reader, config.max_body_size) {
+            Ok(Some(request)) => request,
+            Ok(None) => break,
+            Err(error) if is_idle_timeout(&error) => break,
+            Err(error) => {
+                // the rest of the stream can not be framed after a bad request so the connection is closed.
+                // a failed read means the client is gone and there is nobody left to answer
+                if !is_client_gone(&error) {
+                    let status = error
+                        .downcast_ref::<HttpError>()
+                        .map_or(StatusCode::BAD_REQUEST, HttpError::status);
+                    let mut response = handler.render_error(None, status);
+                    response.set_header(HeaderName::CONNECTION, "close");
+                    response.send(&mut writer)?;
+                    writer.flush()?;
+                }
+                return Err(error);
+            }
+        };
+        request.set_addresses(peer_addr, local_addr);
 
-    response.send(&mut writer)?;
+        let keep_alive =
+            request.keep_alive() && requests_served < config.max_requests_per_connection;
+
+        // a handler that panics only costs this connection, not the worker or the process
+        let (mut response, keep_alive) =
+            match panic::catch_unwind(AssertUnwindSafe(|| handler.handle(&mut request))) {
+                Ok(Ok(response)) => (response, keep_alive),
+                Ok(Err(error)) => {
+                    println!("{} {}\n{:?}", "Error handling".red(), request.path(), error);
+                    let status = error_response(&error).status();
+                    (handler.render_error(Some(&request), status), keep_alive)
+                }
+                Err(_) => {
+                    println!("{} {}", "Handler panicked on".red(), request.path());
+                    let status = StatusCode::INTERNAL_SERVER_ERROR;
+                    (handler.render_error(Some(&request), status), false)
+                }
+            };
+        response.set_version(request.version());
+        let keep_alive = keep_alive && !response.is_close_delimited();
+        if keep_alive {
+            response.set_header(HeaderName::CONNECTION, "keep-alive");
+            response.set_header(
+                HeaderName::KEEP_ALIVE,
+                format!(
+                    "timeout={}, max={}",
+                    config.keep_alive_timeout.as_secs(),
+                    config.max_requests_per_connection - requests_served
+                ),
+            );
+        } else {
+            response.set_header(HeaderName::CONNECTION, "close");
+        }
+
+        if request.method() == Method::Head {
+            response.clear_body();
+        }
+        response.send(&mut writer)?;
+        writer.flush()?;
+
+        if !keep_alive {
+            break;
+        }
+    }
 
     return Ok(());
 }
 
+/// The response for an error a [Handler] returned, the status of an [HttpError] or `500 Internal Server Error`
+/// for anything else. The error itself is left out so internal details don't leak to the client
+pub fn error_response(error: &Report) -> Response {
+    return Response::empty(
+        error
+            .downcast_ref::<HttpError>()
+            .map_or(StatusCode::INTERNAL_SERVER_ERROR, HttpError::status),
+    );
+}
+
+/// Answers a connection the server has no room for with `handler`'s `503 Service Unavailable` and closes it.
+/// Meant for the connections [thread_pool::ThreadPool::try_execute] hands back when its queue is full
+pub fn reject_connection(
+    (_reader, mut writer): (BufReader<TcpStream>, BufWriter<TcpStream>),
+    handler: &dyn Handler,
+) -> Result<(), Report> {
+    let mut response = handler.render_error(None, StatusCode::SERVICE_UNAVAILABLE);
+    response.set_header(HeaderName::RETRY_AFTER, "1");
+    response.set_header(HeaderName::CONNECTION, "close");
+    response.send(&mut writer)?;
+    writer.flush()?;
+    return Ok(());
+}
+
+/// A read that timed out means the connection sat idle for longer than [Config::keep_alive_timeout]
+fn is_idle_timeout(error: &Report) -> bool {
+    return error
+        .downcast_ref::<std::io::Error>()
+        .map(|io_error| matches!(io_error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut))
+        .unwrap_or(false);
+}
+
+/// A read that failed for any reason but bytes that are not UTF-8 means the client can't be answered anymore
+fn is_client_gone(error: &Report) -> bool {
+    return error
+        .downcast_ref::<std::io::Error>()
+        .map(|io_error| io_error.kind() != ErrorKind::InvalidData)
+        .unwrap_or(false);
+}
+
 /// This function is meant to be used as the `predicate` for a [Iterator::filter_map] on [std::net::TcpListener::incoming]
 /// # Example
-/// ```rust
+/// ```rust,no_run
+/// # use scratchserver::split_stream;
+/// # use std::net::TcpListener;
+/// # fn main() -> std::io::Result<()> {
+/// # let ip = "localhost:8000";
 /// let listener = TcpListener::bind(ip)?;
 /// for (reader, writer) in listener.incoming().filter_map(split_stream) {
 ///     // use the split stream
 /// }
+/// # Ok(())
+/// # }
 /// ```
 pub fn split_stream(
     possible_stream: Result<TcpStream, std::io::Error>,
@@ -49,3 +178,67 @@ pub fn split_stream(
             .map(|clone| (BufReader::new(clone), BufWriter::new(stream)))
     });
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use color_eyre::eyre::eyre;
+    use std::{
+        io::Read,
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    /// Sends `raw` over one connection to a server running `handle_connection` and returns everything it answered
+    fn exchange(raw: &'static [u8]) -> String {
+        let listener = TcpListener::bind("localhost:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server_task = thread::spawn(move || {
+            let handler = |request: &mut Request| match request.path() {
+                "/error" => Err(eyre!("the database is on fire")),
+                "/panic" => panic!("the handler panicked"),
+                _ => Ok(Response::text("hello")),
+            };
+            let connection = split_stream(listener.incoming().next().unwrap()).unwrap();
+            let _ = handle_connection(connection, &Config::default(), &handler);
+        });
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(raw).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        server_task.join().unwrap();
+        return response;
+    }
+
+    #[test]
+    fn test_errors_become_responses() {
+        let response = exchange(
+            b"GET /error HTTP/1.1\r\n\r\nGET /panic HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\n\r\n",
+        );
+        let statuses = response
+            .lines()
+            .filter(|line| line.starts_with("HTTP/1.1"))
+            .collect::<Vec<_>>();
+        // the panic closes the connection so the last request is never answered
+        assert_eq!(
+            statuses,
+            [
+                "HTTP/1.1 500 Internal Server Error",
+                "HTTP/1.1 500 Internal Server Error"
+            ]
+        );
+        assert!(!response.contains("on fire"));
+
+        assert!(exchange(b"NONSENSE\r\n\r\n").starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(exchange(b"GET /\xff HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    }
+
+    #[test]
+    fn test_head_has_no_body() {
+        let response = exchange(b"HEAD /hello HTTP/1.1\r\nConnection: close\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Length: 5\r\n"));
+        assert!(response.ends_with("\r\n\r\n"));
+    }
+}
@@ -1,20 +1,81 @@
-use scratchserver::{handle_connection, split_stream};
+#![allow(clippy::needless_return)]
+
+use scratchserver::{
+    compression::Compression,
+    config::Config,
+    error_pages::ErrorPages,
+    handle_connection,
+    handler::{Chain, Logger},
+    reject_connection,
+    router::Router,
+    split_stream,
+    static_files::{precompress, StaticFiles},
+    thread_pool::ThreadPool,
+};
 
 use color_eyre::{owo_colors::OwoColorize, Report};
-use std::{env, net::TcpListener};
+use std::{env, net::TcpListener, path::PathBuf, sync::Arc};
 
 fn main() -> Result<(), Report> {
     color_eyre::install()?;
 
-    let ip = env::args().nth(1).unwrap_or("localhost:8000".to_string());
-    let listener = TcpListener::bind(&ip)?;
+    let mut args = env::args().skip(1).peekable();
+    if args.peek().is_some_and(|arg| arg == "precompress") {
+        let root = args.nth(1).map_or(Config::default().root, PathBuf::from);
+        let written = precompress(&root)?;
+        println!(
+            "Wrote {} precompressed files under {}",
+            written.len(),
+            root.display()
+        );
+        return Ok(());
+    }
+
+    let config = Config::from_args(args)?;
+    let listener = TcpListener::bind(&config.address)?;
 
-    println!("\n------| Listening on http://localhost:8000 |------");
+    let static_files = config.mounts.iter().try_fold(
+        StaticFiles::new(&config.root)?,
+        |static_files, (prefix, directory)| static_files.mount(prefix, directory),
+    )?;
+    let static_files = config.mime_types.iter().fold(
+        static_files
+            .symlinks(config.symlinks)
+            .index_files(config.index_files.clone())
+            .autoindex(config.autoindex)
+            .show_hidden(config.show_hidden),
+        |static_files, (extension, media_type)| static_files.mime_type(extension, media_type),
+    );
+    // error pages inside the chain, so the pages they fill in are logged and compressed like any other body
+    let handler = Arc::new(
+        Chain::new(
+            ErrorPages::new(Router::new().fallback(static_files), &config.root)
+                .dev_mode(config.dev_mode),
+        )
+        .layer(Logger)
+        .layer(Compression::new()),
+    );
 
-    for connection in listener.incoming().filter_map(split_stream) {
-        if let Err(error) = handle_connection(connection) {
+    let address = config.address.clone();
+    let pool_handler = Arc::clone(&handler);
+    let pool = ThreadPool::new(config.worker_count, config.queue_depth, move |connection| {
+        if let Err(error) = handle_connection(connection, &config, pool_handler.as_ref()) {
             println!("Error handling connection: {}", error.on_red());
         }
+    })?;
+
+    println!(
+        "\n------| Listening on http://{} with {} workers |------",
+        address,
+        pool.worker_count()
+    );
+
+    for connection in listener.incoming().filter_map(split_stream) {
+        if let Err(connection) = pool.try_execute(connection) {
+            if let Err(error) = reject_connection(connection, handler.as_ref()) {
+                println!("Error rejecting connection: {}", error.on_red());
+            }
+        }
     }
 
     return Ok(());
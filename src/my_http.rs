@@ -1,15 +1,39 @@
+mod accept;
 mod body;
+mod conditional;
+mod date;
+mod encoding;
+mod error;
+mod extensions;
+mod header;
 mod method;
+mod mime;
+mod range;
 mod request;
 mod response;
 mod url;
 
-pub use self::{method::Method, request::Request, response::Response, url::Url};
+pub use self::{
+    accept::{escape_html, escape_json, preferred_media_type},
+    body::{Body, Chunks},
+    conditional::check_preconditions,
+    date::HttpDate,
+    encoding::{AcceptEncoding, ContentCoding},
+    error::HttpError,
+    extensions::Extensions,
+    header::{HeaderMap, HeaderName, HeaderValue},
+    method::Method,
+    mime::MimeTypes,
+    range::{ByteRange, RangeRequest},
+    request::Request,
+    response::{Response, ResponseBuilder, StatusCode},
+    url::{percent_decode, Url},
+};
 
 use color_eyre::{eyre::eyre, Report};
 use std::{fmt::Display, str::FromStr};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Version {
     Http0_9,
     Http1_0,
@@ -0,0 +1,103 @@
+use crate::my_http::{HeaderName, Request};
+
+/// Picks the media type out of `available` that the request's `Accept` header weighs highest, the most specific
+/// matching range deciding the weight of each type. Ties go to the one listed first in `available`.
+///
+/// Without an `Accept` header the first type is picked, [None] means the client accepts none of them
+pub fn preferred_media_type<'a>(request: &Request, available: &[&'a str]) -> Option<&'a str> {
+    if !request.headers().contains(HeaderName::ACCEPT) {
+        return available.first().copied();
+    }
+
+    let mut weights = vec![(0, 0); available.len()];
+    for element in request.headers().get_list(HeaderName::ACCEPT) {
+        let mut parameters = element.split(';');
+        let media_range = parameters.next().unwrap_or_default().trim().to_lowercase();
+        let weight = parameters
+            .filter_map(|parameter| parameter.split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+            .and_then(|(_, q)| q.trim().parse::<f32>().ok())
+            .map_or(1000, |q| (q.clamp(0.0, 1.0) * 1000.0) as u16);
+
+        for (media_type, (best, best_specificity)) in available.iter().zip(weights.iter_mut()) {
+            let specificity = match media_range.as_str() {
+                range if range.eq_ignore_ascii_case(media_type) => 3,
+                "*/*" => 1,
+                range => match range.strip_suffix("/*") {
+                    Some(top_level) if media_type.starts_with(&format!("{}/", top_level)) => 2,
+                    _ => 0,
+                },
+            };
+            if specificity > *best_specificity {
+                *best = weight;
+                *best_specificity = specificity;
+            }
+        }
+    }
+
+    let mut preferred = None;
+    for (media_type, (weight, _)) in available.iter().zip(weights) {
+        if weight > 0 && preferred.is_none_or(|(_, best)| weight > best) {
+            preferred = Some((*media_type, weight));
+        }
+    }
+    return preferred.map(|(media_type, _)| media_type);
+}
+
+/// Escapes `text` for use in html text and quoted attribute values
+pub fn escape_html(text: &str) -> String {
+    return text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;");
+}
+
+/// Escapes `text` for use inside a json string
+pub fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            character if character < ' ' => {
+                escaped.push_str(&format!("\\u{:04x}", character as u32));
+            }
+            character => escaped.push(character),
+        }
+    }
+    return escaped;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn preferred(accept: &str) -> Option<&'static str> {
+        let request: Request = format!("GET / HTTP/1.1\r\nAccept: {}\r\n\r\n", accept)
+            .parse()
+            .unwrap();
+        return preferred_media_type(&request, &["text/html", "application/json"]);
+    }
+
+    #[test]
+    fn test_preferred_media_type() {
+        let request: Request = "GET / HTTP/1.1\r\n\r\n".parse().unwrap();
+        assert_eq!(
+            preferred_media_type(&request, &["text/html", "application/json"]),
+            Some("text/html")
+        );
+        assert_eq!(preferred("application/json"), Some("application/json"));
+        assert_eq!(
+            preferred("text/html;q=0.5, application/*"),
+            Some("application/json")
+        );
+        assert_eq!(preferred("*/*, application/json;q=0.1"), Some("text/html"));
+        assert_eq!(preferred("*/*"), Some("text/html"));
+        assert_eq!(preferred("image/png"), None);
+    }
+}
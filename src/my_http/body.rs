@@ -1,41 +1,150 @@
-use std::{borrow::Cow, io::Write, u8};
+use crate::my_http::{ByteRange, ContentCoding};
 
-use flate2::{write::GzEncoder, Compression};
+use std::{
+    borrow::Cow,
+    fmt::Debug,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    iter,
+};
+
+/// The chunks of a [Body::Stream], produced one at a time while the response is being sent
+pub type Chunks = Box<dyn Iterator<Item = Result<Vec<u8>, io::Error>> + Send>;
 
-#[derive(Debug, Clone)]
 pub enum Body {
     String(String),
     Data(Vec<u8>),
+    /// A body of unknown length that is generated while it is sent.
+    /// It goes out with `Transfer-Encoding: chunked`, or delimited by closing the connection for HTTP/1.0 clients
+    Stream(Chunks),
+    /// `len` bytes of an open file starting at `offset`, copied to the connection while it is sent.
+    /// On Linux the copy happens in the kernel through `sendfile` or `splice`
+    File {
+        file: File,
+        offset: u64,
+        len: u64,
+    },
 }
 impl Body {
-    pub fn len(&self) -> usize {
+    /// The number of bytes to read at a time in [Body::from_reader]
+    pub const CHUNK_SIZE: usize = 8 * 1024;
+
+    /// A streamed body where every item of `chunks` is sent as one chunk
+    pub fn from_chunks<I>(chunks: I) -> Self
+    where
+        I: IntoIterator<Item = Result<Vec<u8>, io::Error>>,
+        I::IntoIter: Send + 'static,
+    {
+        return Body::Stream(Box::new(chunks.into_iter()));
+    }
+    /// A streamed body that reads `reader` to the end, [Body::CHUNK_SIZE] bytes at a time
+    pub fn from_reader<R: Read + Send + 'static>(reader: R) -> Self {
+        return Body::Stream(Box::new(ReadChunks { reader }));
+    }
+    /// A body holding the whole of `file`, which is read while the response is sent
+    pub fn from_file(file: File) -> Result<Self, io::Error> {
+        let len = file.metadata()?.len();
+        return Ok(Body::File {
+            file,
+            offset: 0,
+            len,
+        });
+    }
+    /// The number of bytes in the body, or [None] for a [Body::Stream]
+    pub fn len(&self) -> Option<usize> {
         return match self {
-            Body::String(s) => s.len(),
-            Body::Data(s) => s.len(),
+            Body::String(s) => Some(s.len()),
+            Body::Data(s) => Some(s.len()),
+            Body::Stream(_) => None,
+            Body::File { len, .. } => Some(*len as usize),
         };
     }
-    ///  Returns the encoded data as a [Cow]<[str]>
-    pub fn encode(&self) -> Cow<[u8]> {
-        let mut encoder = GzEncoder::new(Vec::with_capacity(self.len()), Compression::default());
-
-        return match encoder
-            .write_all(self.as_ref())
-            .and_then(|_| encoder.finish())
-        {
-            Ok(encoded_body) => Cow::from(encoded_body),
-            Err(_) => Cow::from(self.as_ref()),
+    pub fn is_empty(&self) -> bool {
+        return self.len() == Some(0);
+    }
+    pub fn is_stream(&self) -> bool {
+        return matches!(self, Body::Stream(_));
+    }
+    pub fn is_file(&self) -> bool {
+        return matches!(self, Body::File { .. });
+    }
+    /// The bytes of the body, reading a [Body::File] into memory. A [Body::Stream] has none until it is sent
+    pub fn read_bytes(&self) -> Result<Cow<'_, [u8]>, io::Error> {
+        return match self {
+            Body::File { file, offset, len } => {
+                let mut file = file;
+                file.seek(SeekFrom::Start(*offset))?;
+                let mut data = Vec::with_capacity(*len as usize);
+                file.take(*len).read_to_end(&mut data)?;
+                Ok(Cow::from(data))
+            }
+            body => Ok(body.as_cow_u8()),
         };
     }
-    pub fn as_cow_u8(&self) -> Cow<[u8]> {
+    /// The bytes in `range`, a [Body::File] shares the open file instead of reading it.
+    /// Fails for a [Body::Stream] and for ranges past the end of the body
+    pub fn slice(&self, range: ByteRange) -> Result<Body, io::Error> {
+        if self.len().is_none_or(|len| range.end >= len as u64) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("the range {} is not within the body", range),
+            ));
+        }
+        return match self {
+            Body::File { file, offset, .. } => Ok(Body::File {
+                file: file.try_clone()?,
+                offset: offset + range.start,
+                len: range.len(),
+            }),
+            body => Ok(Body::Data(
+                body.as_ref()[range.start as usize..=range.end as usize].to_vec(),
+            )),
+        };
+    }
+    /// The body as the [Chunks] of a [Body::Stream], so it can be sent as one part of a larger stream.
+    /// A [Body::File] is read lazily, from its own offset, as the chunks are taken
+    pub fn into_chunks(self) -> Chunks {
+        return match self {
+            Body::String(s) => Box::new(iter::once(Ok(s.into_bytes()))),
+            Body::Data(d) => Box::new(iter::once(Ok(d))),
+            Body::Stream(chunks) => chunks,
+            Body::File { file, offset, len } => Box::new(ReadChunks {
+                reader: FileRange { file, offset, len },
+            }),
+        };
+    }
+    /// The bytes of the body encoded with `coding`, see [Body::read_bytes]
+    pub fn encode(&self, coding: ContentCoding) -> Result<Vec<u8>, io::Error> {
+        return coding.encode(&self.read_bytes()?);
+    }
+    /// Copies a [Body::File] to `writer`. Generic so [io::copy] can see both ends and use zero-copy system calls
+    pub(crate) fn copy_file_to<W: Write>(&self, writer: &mut W) -> Result<(), io::Error> {
+        let Body::File { file, offset, len } = self else {
+            return Ok(());
+        };
+        let mut file = file;
+        file.seek(SeekFrom::Start(*offset))?;
+        let copied = io::copy(&mut file.take(*len), writer)?;
+        if copied < *len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "the file was shorter than its body length",
+            ));
+        }
+        return Ok(());
+    }
+    pub fn as_cow_u8(&self) -> Cow<'_, [u8]> {
         return match self {
             Body::String(s) => Cow::from(s.as_bytes()),
             Body::Data(d) => Cow::from(d),
+            Body::Stream(_) | Body::File { .. } => Cow::from(&[][..]),
         };
     }
-    pub fn as_cow_str(&self) -> Cow<str> {
+    pub fn as_cow_str(&self) -> Cow<'_, str> {
         return match self {
             Body::String(s) => Cow::from(s.as_str()),
             Body::Data(d) => String::from_utf8_lossy(d.as_ref()),
+            Body::Stream(_) | Body::File { .. } => Cow::from(""),
         };
     }
 }
@@ -48,6 +157,16 @@ impl From<Vec<u8>> for Body {
         };
     }
 }
+impl From<String> for Body {
+    fn from(value: String) -> Self {
+        return Body::String(value);
+    }
+}
+impl From<&str> for Body {
+    fn from(value: &str) -> Self {
+        return Body::String(value.to_string());
+    }
+}
 impl From<&[u8]> for Body {
     /// Clone a [AsRef<u8>] into either a [String] or a [Vec]
     fn from(data: &[u8]) -> Self {
@@ -59,10 +178,70 @@ impl From<&[u8]> for Body {
 }
 
 impl AsRef<[u8]> for Body {
+    /// The bytes held in memory. A [Body::Stream] or [Body::File] has none until it is sent
     fn as_ref(&self) -> &[u8] {
         return match self {
             Body::String(s) => s.as_bytes(),
             Body::Data(d) => d.as_ref(),
+            Body::Stream(_) | Body::File { .. } => &[],
+        };
+    }
+}
+impl Debug for Body {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return match self {
+            Body::String(s) => f.debug_tuple("String").field(s).finish(),
+            Body::Data(d) => f.debug_tuple("Data").field(d).finish(),
+            Body::Stream(_) => f.debug_tuple("Stream").finish_non_exhaustive(),
+            Body::File { file, offset, len } => f
+                .debug_struct("File")
+                .field("file", file)
+                .field("offset", offset)
+                .field("len", len)
+                .finish(),
+        };
+    }
+}
+
+/// Adapts a [Read] into the [Chunks] of a [Body::Stream]
+struct ReadChunks<R> {
+    reader: R,
+}
+impl<R: Read> Iterator for ReadChunks<R> {
+    type Item = Result<Vec<u8>, io::Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chunk = vec![0; Body::CHUNK_SIZE];
+        loop {
+            return match self.reader.read(&mut chunk) {
+                Ok(0) => None,
+                Ok(bytes_read) => {
+                    chunk.truncate(bytes_read);
+                    Some(Ok(chunk))
+                }
+                Err(io_error) if io_error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(io_error) => Some(Err(io_error)),
+            };
+        }
+    }
+}
+
+/// Reads `len` bytes of `file` starting at `offset`.
+/// Seeks before every read because a [File::try_clone] shares its cursor with the other [Body::File]s of the same file
+struct FileRange {
+    file: File,
+    offset: u64,
+    len: u64,
+}
+impl Read for FileRange {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let max = buf.len().min(self.len as usize);
+        if max == 0 {
+            return Ok(0);
         }
+        self.file.seek(SeekFrom::Start(self.offset))?;
+        let bytes_read = self.file.read(&mut buf[..max])?;
+        self.offset += bytes_read as u64;
+        self.len -= bytes_read as u64;
+        return Ok(bytes_read);
     }
 }
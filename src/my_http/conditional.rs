@@ -0,0 +1,179 @@
+use crate::my_http::{HeaderName, HttpDate, Method, Request, Response, StatusCode};
+
+/// Headers a `304 Not Modified` repeats from the response it stands in for
+const NOT_MODIFIED_HEADERS: &[HeaderName] = &[
+    HeaderName::CACHE_CONTROL,
+    HeaderName::DATE,
+    HeaderName::ETAG,
+    HeaderName::LAST_MODIFIED,
+    HeaderName::VARY,
+];
+
+/// Evaluates the `If-Match`, `If-Unmodified-Since`, `If-None-Match` and `If-Modified-Since` headers of `request`
+/// against the `ETag` and `Last-Modified` of `response`, in the order of RFC 9110 section 13.2.2.
+///
+/// Returns the `304 Not Modified` or `412 Precondition Failed` to send instead of `response`,
+/// or [None] when `response` should be sent as it is
+pub fn check_preconditions(request: &Request, response: &Response) -> Option<Response> {
+    let headers = request.headers();
+    let etag = response
+        .headers()
+        .get(HeaderName::ETAG)
+        .map(|etag| etag.as_str());
+    let last_modified = response
+        .headers()
+        .get(HeaderName::LAST_MODIFIED)
+        .and_then(|last_modified| last_modified.parse::<HttpDate>());
+    let is_get_or_head = matches!(request.method(), Method::Get | Method::Head);
+
+    if headers.contains(HeaderName::IF_MATCH) {
+        if !matches_any(request, HeaderName::IF_MATCH, etag, strong_eq) {
+            return Some(Response::empty(StatusCode::PRECONDITION_FAILED));
+        }
+    } else if let Some(if_unmodified_since) = request_date(request, HeaderName::IF_UNMODIFIED_SINCE)
+    {
+        if last_modified.is_none_or(|last_modified| last_modified > if_unmodified_since) {
+            return Some(Response::empty(StatusCode::PRECONDITION_FAILED));
+        }
+    }
+
+    if headers.contains(HeaderName::IF_NONE_MATCH) {
+        if matches_any(request, HeaderName::IF_NONE_MATCH, etag, weak_eq) {
+            return Some(if is_get_or_head {
+                not_modified(response)
+            } else {
+                Response::empty(StatusCode::PRECONDITION_FAILED)
+            });
+        }
+    } else if let Some(if_modified_since) = request_date(request, HeaderName::IF_MODIFIED_SINCE) {
+        if is_get_or_head
+            && last_modified.is_some_and(|last_modified| last_modified <= if_modified_since)
+        {
+            return Some(not_modified(response));
+        }
+    }
+
+    return None;
+}
+
+/// Whether the entity tags in the `name` header of `request` include `etag`, `*` matches any current representation
+fn matches_any(
+    request: &Request,
+    name: HeaderName,
+    etag: Option<&str>,
+    eq: fn(&str, &str) -> bool,
+) -> bool {
+    return request
+        .headers()
+        .get_list(name)
+        .any(|candidate| candidate == "*" || etag.is_some_and(|etag| eq(candidate, etag)));
+}
+
+/// Both tags are strong and their opaque parts are the same
+fn strong_eq(a: &str, b: &str) -> bool {
+    return !a.starts_with("W/") && !b.starts_with("W/") && a == b;
+}
+
+/// The opaque parts are the same, whether either tag is weak or not
+fn weak_eq(a: &str, b: &str) -> bool {
+    return a.trim_start_matches("W/") == b.trim_start_matches("W/");
+}
+
+/// A date header of `request`, an invalid date is treated as if the header was not sent
+fn request_date(request: &Request, name: HeaderName) -> Option<HttpDate> {
+    return request.header(name).and_then(|date| date.parse().ok());
+}
+
+/// A `304 Not Modified` carrying the validators and cache headers of `response`, but not its body or `Content-Length`
+fn not_modified(response: &Response) -> Response {
+    let mut not_modified = Response::empty(StatusCode::NOT_MODIFIED);
+    not_modified
+        .headers_mut()
+        .remove(HeaderName::CONTENT_LENGTH);
+    for (name, value) in response.headers() {
+        if NOT_MODIFIED_HEADERS.contains(name) {
+            not_modified.append_header(name, value.clone());
+        }
+    }
+    return not_modified;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const LAST_MODIFIED: &str = "Sun, 06 Nov 1994 08:49:37 GMT";
+
+    fn check(method: &str, headers: &str) -> Option<StatusCode> {
+        let request: Request = format!("{} / HTTP/1.1\r\n{}\r\n", method, headers)
+            .parse()
+            .unwrap();
+        let response = Response::builder()
+            .header(HeaderName::ETAG, "\"abc\"")
+            .header(HeaderName::LAST_MODIFIED, LAST_MODIFIED)
+            .body("body");
+        return check_preconditions(&request, &response).map(|response| response.status());
+    }
+
+    #[test]
+    fn test_precondition_precedence() {
+        assert_eq!(check("GET", ""), None);
+        assert_eq!(
+            check("GET", "If-None-Match: \"xyz\", W/\"abc\"\r\n"),
+            Some(StatusCode::NOT_MODIFIED)
+        );
+        assert_eq!(
+            check("GET", "If-None-Match: *\r\n"),
+            Some(StatusCode::NOT_MODIFIED)
+        );
+        assert_eq!(
+            check("PUT", "If-None-Match: *\r\n"),
+            Some(StatusCode::PRECONDITION_FAILED)
+        );
+        assert_eq!(check("GET", "If-None-Match: \"xyz\"\r\n"), None);
+
+        assert_eq!(
+            check("GET", &format!("If-Modified-Since: {}\r\n", LAST_MODIFIED)),
+            Some(StatusCode::NOT_MODIFIED)
+        );
+        assert_eq!(
+            check(
+                "GET",
+                "If-Modified-Since: Sat, 05 Nov 1994 08:49:37 GMT\r\n"
+            ),
+            None
+        );
+        // If-None-Match takes precedence over If-Modified-Since
+        assert_eq!(
+            check(
+                "GET",
+                &format!(
+                    "If-None-Match: \"xyz\"\r\nIf-Modified-Since: {}\r\n",
+                    LAST_MODIFIED
+                )
+            ),
+            None
+        );
+
+        assert_eq!(check("PUT", "If-Match: \"abc\"\r\n"), None);
+        assert_eq!(
+            check("PUT", "If-Match: W/\"abc\"\r\n"),
+            Some(StatusCode::PRECONDITION_FAILED)
+        );
+        assert_eq!(
+            check(
+                "PUT",
+                "If-Unmodified-Since: Sat, 05 Nov 1994 08:49:37 GMT\r\n"
+            ),
+            Some(StatusCode::PRECONDITION_FAILED)
+        );
+        // If-Match takes precedence over If-Unmodified-Since
+        assert_eq!(
+            check(
+                "PUT",
+                "If-Match: *\r\nIf-Unmodified-Since: Sat, 05 Nov 1994 08:49:37 GMT\r\n"
+            ),
+            None
+        );
+    }
+}
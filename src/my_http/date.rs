@@ -0,0 +1,191 @@
+use color_eyre::{eyre::eyre, Report};
+use std::{
+    fmt::Display,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// A point in time with the one second precision of http dates, like `Sun, 06 Nov 1994 08:49:37 GMT`.
+///
+/// Displays as an IMF-fixdate and parses the IMF-fixdate, RFC 850 and asctime formats, all in GMT.
+/// Only dates after the unix epoch can be represented
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HttpDate {
+    secs_since_epoch: u64,
+}
+impl HttpDate {
+    pub fn now() -> Self {
+        return HttpDate::from(SystemTime::now());
+    }
+}
+impl From<SystemTime> for HttpDate {
+    /// Truncates to whole seconds, times before the unix epoch become the epoch
+    fn from(time: SystemTime) -> Self {
+        let secs_since_epoch = time
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_secs())
+            .unwrap_or_default();
+        return HttpDate { secs_since_epoch };
+    }
+}
+impl From<HttpDate> for SystemTime {
+    fn from(date: HttpDate) -> Self {
+        return UNIX_EPOCH + Duration::from_secs(date.secs_since_epoch);
+    }
+}
+impl Display for HttpDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let days = (self.secs_since_epoch / 86400) as i64;
+        let secs_of_day = self.secs_since_epoch % 86400;
+        let (year, month, day) = civil_from_days(days);
+        // the epoch was a thursday
+        let weekday = DAYS[((days + 4) % 7) as usize];
+
+        return write!(
+            f,
+            "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+            weekday,
+            day,
+            MONTHS[month as usize - 1],
+            year,
+            secs_of_day / 3600,
+            secs_of_day / 60 % 60,
+            secs_of_day % 60
+        );
+    }
+}
+impl FromStr for HttpDate {
+    type Err = Report;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || eyre!("in HttpDate::from_str {} is not a valid http date", s);
+        let parts = s.split_whitespace().collect::<Vec<_>>();
+
+        let (day, month, year, time) = match parts[..] {
+            // IMF-fixdate: Sun, 06 Nov 1994 08:49:37 GMT
+            [_, day, month, year, time, "GMT"] => {
+                (day, month, year.parse().map_err(|_| invalid())?, time)
+            }
+            // RFC 850: Sunday, 06-Nov-94 08:49:37 GMT
+            [_, date, time, "GMT"] => {
+                let mut date = date.split('-');
+                let (day, month, year) = match (date.next(), date.next(), date.next()) {
+                    (Some(day), Some(month), Some(year)) => (day, month, year),
+                    _ => return Err(invalid()),
+                };
+                let year: i64 = year.parse().map_err(|_| invalid())?;
+                // two digit years are read as the closest one not too far in the future
+                let year = if year >= 70 { 1900 + year } else { 2000 + year };
+                (day, month, year, time)
+            }
+            // asctime: Sun Nov  6 08:49:37 1994
+            [_, month, day, time, year] => (day, month, year.parse().map_err(|_| invalid())?, time),
+            _ => return Err(invalid()),
+        };
+
+        let day: u32 = day.parse().map_err(|_| invalid())?;
+        let month = MONTHS
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(month))
+            .ok_or_else(invalid)? as u32
+            + 1;
+        let time = time
+            .split(':')
+            .map(|part| part.parse::<u64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [hour, minute, second] = time[..] else {
+            return Err(invalid());
+        };
+
+        // four digit years keep the seconds arithmetic below far from overflowing
+        if !(1970..=9999).contains(&year)
+            || !(1..=days_in_month(year, month)).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
+            return Err(invalid());
+        }
+        let days = days_from_civil(year, month, day) as u64;
+        return Ok(HttpDate {
+            secs_since_epoch: days * 86400 + hour * 3600 + minute * 60 + second,
+        });
+    }
+}
+
+/// How many days `month` has in `year`, counting February 29th in gregorian leap years
+fn days_in_month(year: i64, month: u32) -> u32 {
+    let leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    return match month {
+        2 if leap_year => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+}
+
+/// Days since the unix epoch of a proleptic gregorian date, after Howard Hinnant's `days_from_civil`
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    return era * 146097 + day_of_era - 719468;
+}
+
+/// The `(year, month, day)` that is `days` after the unix epoch, the inverse of [days_from_civil]
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    return (year, month, day);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_http_date_formats() {
+        let date = HttpDate::from(UNIX_EPOCH + Duration::from_secs(784111777));
+        assert_eq!(date.to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+
+        for formatted in [
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+        ] {
+            assert_eq!(formatted.parse::<HttpDate>().unwrap(), date);
+        }
+
+        let leap_day = "Thu, 29 Feb 2024 23:59:59 GMT";
+        assert_eq!(leap_day.parse::<HttpDate>().unwrap().to_string(), leap_day);
+        assert!("Sun, 06 Nov 1994 25:49:37 GMT".parse::<HttpDate>().is_err());
+        assert!("Sat, 31 Feb 2024 00:00:00 GMT".parse::<HttpDate>().is_err());
+        assert!("Thu, 29 Feb 2023 00:00:00 GMT".parse::<HttpDate>().is_err());
+        assert!("Thu, 31 Apr 2024 00:00:00 GMT".parse::<HttpDate>().is_err());
+        assert!("yesterday".parse::<HttpDate>().is_err());
+        assert!("Sun, 06 Nov 100000000000000 08:49:37 GMT"
+            .parse::<HttpDate>()
+            .is_err());
+        assert!("Sun Nov  6 08:49:37 9223372036854775807"
+            .parse::<HttpDate>()
+            .is_err());
+    }
+}
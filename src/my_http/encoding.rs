@@ -0,0 +1,246 @@
+use color_eyre::{eyre::eyre, Report};
+#[cfg(feature = "deflate")]
+use flate2::write::ZlibEncoder;
+use flate2::{write::GzEncoder, Compression};
+use std::{
+    fmt::Display,
+    io::{self, Write},
+    str::FromStr,
+};
+
+/// A content coding a body can be sent with, named in `Content-Encoding` and `Accept-Encoding`.
+///
+/// `gzip` is always available, `deflate`, `br` and `zstd` need the cargo features of the same names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentCoding {
+    Identity,
+    Gzip,
+    /// The zlib format, which is what `deflate` means in HTTP
+    Deflate,
+    Brotli,
+    Zstd,
+}
+impl ContentCoding {
+    /// The codings this build can produce, best first. Ties in `Accept-Encoding` go to the earlier one
+    pub const SUPPORTED: &'static [ContentCoding] = &[
+        #[cfg(feature = "brotli")]
+        ContentCoding::Brotli,
+        #[cfg(feature = "zstd")]
+        ContentCoding::Zstd,
+        ContentCoding::Gzip,
+        #[cfg(feature = "deflate")]
+        ContentCoding::Deflate,
+        ContentCoding::Identity,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        return match self {
+            ContentCoding::Identity => "identity",
+            ContentCoding::Gzip => "gzip",
+            ContentCoding::Deflate => "deflate",
+            ContentCoding::Brotli => "br",
+            ContentCoding::Zstd => "zstd",
+        };
+    }
+    /// The extension of a file holding a body precompressed with this coding, like `ducks.gif.gz`
+    pub fn file_extension(&self) -> Option<&'static str> {
+        return match self {
+            ContentCoding::Gzip => Some("gz"),
+            ContentCoding::Brotli => Some("br"),
+            ContentCoding::Zstd => Some("zst"),
+            ContentCoding::Identity | ContentCoding::Deflate => None,
+        };
+    }
+    pub fn is_supported(&self) -> bool {
+        return ContentCoding::SUPPORTED.contains(self);
+    }
+    /// Encodes all of `data` with this coding, fails with [io::ErrorKind::Unsupported]
+    /// when the feature it needs is not enabled
+    pub fn encode(&self, data: &[u8]) -> Result<Vec<u8>, io::Error> {
+        let output = Vec::with_capacity(data.len() / 2);
+        return match self {
+            ContentCoding::Identity => Ok(data.to_vec()),
+            ContentCoding::Gzip => {
+                let mut encoder = GzEncoder::new(output, Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "deflate")]
+            ContentCoding::Deflate => {
+                let mut encoder = ZlibEncoder::new(output, Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "brotli")]
+            ContentCoding::Brotli => {
+                let mut encoder = brotli::CompressorWriter::new(output, 4096, 5, 22);
+                encoder.write_all(data)?;
+                encoder.flush()?;
+                Ok(encoder.into_inner())
+            }
+            #[cfg(feature = "zstd")]
+            ContentCoding::Zstd => zstd::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL),
+            #[allow(unreachable_patterns)]
+            unsupported => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "the {} content coding is not enabled in this build",
+                    unsupported
+                ),
+            )),
+        };
+    }
+}
+impl FromStr for ContentCoding {
+    type Err = Report;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s.trim().to_lowercase().as_str() {
+            "identity" => Ok(ContentCoding::Identity),
+            "gzip" | "x-gzip" => Ok(ContentCoding::Gzip),
+            "deflate" => Ok(ContentCoding::Deflate),
+            "br" => Ok(ContentCoding::Brotli),
+            "zstd" => Ok(ContentCoding::Zstd),
+            invalid => Err(eyre!("{} is not a supported content coding", invalid)),
+        };
+    }
+}
+impl Display for ContentCoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", self.as_str());
+    }
+}
+
+/// The preferences a client sent in `Accept-Encoding`, like `gzip;q=1.0, identity;q=0.5, *;q=0`
+#[derive(Debug, Clone, Default)]
+pub struct AcceptEncoding {
+    /// Each coding name, lower cased, with its weight from 0 to 1000
+    preferences: Vec<(String, u16)>,
+}
+impl AcceptEncoding {
+    /// Parses the elements of every `Accept-Encoding` field. Elements that can not be parsed are skipped
+    pub fn parse<'a>(elements: impl IntoIterator<Item = &'a str>) -> Self {
+        let preferences = elements
+            .into_iter()
+            .filter_map(|element| {
+                let mut parameters = element.split(';');
+                let coding = parameters.next()?.trim().to_lowercase();
+                let mut weight = 1000;
+                for parameter in parameters {
+                    if let Some((name, value)) = parameter.split_once('=') {
+                        if name.trim().eq_ignore_ascii_case("q") {
+                            weight = parse_weight(value.trim())?;
+                        }
+                    }
+                }
+                Some((coding, weight))
+            })
+            .collect();
+
+        return AcceptEncoding { preferences };
+    }
+    /// The weight the client gave `coding`, falling back to `*`.
+    /// `identity` is acceptable unless it is refused by name or through `*`
+    pub fn weight(&self, coding: ContentCoding) -> u16 {
+        let named = |name: &str| {
+            self.preferences
+                .iter()
+                .find(|(preference, _)| preference == name)
+                .map(|(_, weight)| *weight)
+        };
+
+        return named(coding.as_str())
+            .or_else(|| match coding {
+                ContentCoding::Gzip => named("x-gzip"),
+                _ => None,
+            })
+            .or_else(|| named("*"))
+            .unwrap_or(match coding {
+                ContentCoding::Identity => 1,
+                _ => 0,
+            });
+    }
+    /// The acceptable coding with the highest weight out of `available`, which is in server preference order.
+    /// [None] when the client accepts none of them, not even `identity`
+    pub fn negotiate(&self, available: &[ContentCoding]) -> Option<ContentCoding> {
+        let mut best = None;
+        for &coding in available {
+            let weight = self.weight(coding);
+            if weight > 0 && best.is_none_or(|(_, best_weight)| weight > best_weight) {
+                best = Some((coding, weight));
+            }
+        }
+        return best.map(|(coding, _)| coding);
+    }
+}
+
+/// Parses a qvalue like `0.8` into thousandths so weights compare exactly
+fn parse_weight(value: &str) -> Option<u16> {
+    let weight = value.parse::<f32>().ok()?;
+    if !(0.0..=1.0).contains(&weight) {
+        return None;
+    }
+    return Some((weight * 1000.0).round() as u16);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn negotiate(header: &str) -> Option<ContentCoding> {
+        return AcceptEncoding::parse(header.split(',')).negotiate(ContentCoding::SUPPORTED);
+    }
+
+    #[test]
+    fn test_negotiate_honours_q_values() {
+        assert_eq!(negotiate("gzip, compress"), Some(ContentCoding::Gzip));
+        assert_eq!(negotiate("br;q=0.2, gzip;q=0.5"), Some(ContentCoding::Gzip));
+        assert_eq!(
+            negotiate("gzip;q=0.5, identity"),
+            Some(ContentCoding::Identity)
+        );
+        assert_eq!(negotiate("gzip;q=0"), Some(ContentCoding::Identity));
+        assert_eq!(negotiate("*"), Some(ContentCoding::SUPPORTED[0]));
+        assert_eq!(negotiate(""), Some(ContentCoding::Identity));
+        assert_eq!(negotiate("compress, *;q=0"), None);
+        assert_eq!(
+            negotiate("identity;q=0, gzip;q=0.1"),
+            Some(ContentCoding::Gzip)
+        );
+    }
+
+    #[test]
+    fn test_supported_codings_round_trip() {
+        use flate2::read::{GzDecoder, ZlibDecoder};
+        use std::io::Read;
+
+        let data = "quack ".repeat(100);
+        for coding in ContentCoding::SUPPORTED {
+            let encoded = coding.encode(data.as_bytes()).unwrap();
+            let mut decoded = Vec::new();
+            match coding {
+                ContentCoding::Identity => decoded = encoded,
+                ContentCoding::Gzip => {
+                    GzDecoder::new(&encoded[..])
+                        .read_to_end(&mut decoded)
+                        .unwrap();
+                }
+                ContentCoding::Deflate => {
+                    ZlibDecoder::new(&encoded[..])
+                        .read_to_end(&mut decoded)
+                        .unwrap();
+                }
+                #[cfg(feature = "brotli")]
+                ContentCoding::Brotli => {
+                    brotli::Decompressor::new(&encoded[..], 4096)
+                        .read_to_end(&mut decoded)
+                        .unwrap();
+                }
+                #[cfg(feature = "zstd")]
+                ContentCoding::Zstd => decoded = zstd::decode_all(&encoded[..]).unwrap(),
+                #[allow(unreachable_patterns)]
+                _ => unreachable!("{} is not supported", coding),
+            }
+            assert_eq!(decoded, data.as_bytes(), "{} did not round trip", coding);
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use crate::my_http::StatusCode;
+
+use std::fmt::Display;
+
+/// An error that should be answered with a specific [StatusCode] instead of dropping the connection.
+///
+/// It travels inside a [color_eyre::Report] like any other error and can be recovered with
+/// [color_eyre::Report::downcast_ref]
+#[derive(Debug)]
+pub struct HttpError {
+    status: StatusCode,
+    message: String,
+}
+impl HttpError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        return HttpError {
+            status,
+            message: message.into(),
+        };
+    }
+    pub fn status(&self) -> StatusCode {
+        return self.status;
+    }
+    pub fn message(&self) -> &str {
+        return &self.message;
+    }
+}
+impl Display for HttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}: {}", self.status, self.message);
+    }
+}
+impl std::error::Error for HttpError {}
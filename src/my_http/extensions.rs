@@ -0,0 +1,62 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::Debug,
+};
+
+/// Values attached to a request, keyed by their type, so middleware can hand data to the handlers after it.
+///
+/// Wrap values in a type of your own, holding one `String` here would clash with everything else that does
+#[derive(Default)]
+pub struct Extensions {
+    values: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+impl Extensions {
+    pub fn new() -> Self {
+        return Extensions::default();
+    }
+    /// Stores `value`, returning the previous value of the same type
+    pub fn insert<T: Any + Send + Sync>(&mut self, value: T) -> Option<T> {
+        return self
+            .values
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|previous| previous.downcast().ok())
+            .map(|previous| *previous);
+    }
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        return self
+            .values
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref());
+    }
+    pub fn get_mut<T: Any + Send + Sync>(&mut self) -> Option<&mut T> {
+        return self
+            .values
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_mut());
+    }
+    pub fn remove<T: Any + Send + Sync>(&mut self) -> Option<T> {
+        return self
+            .values
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast().ok())
+            .map(|value| *value);
+    }
+    pub fn contains<T: Any + Send + Sync>(&self) -> bool {
+        return self.values.contains_key(&TypeId::of::<T>());
+    }
+    pub fn len(&self) -> usize {
+        return self.values.len();
+    }
+    pub fn is_empty(&self) -> bool {
+        return self.values.is_empty();
+    }
+}
+impl Debug for Extensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return f
+            .debug_struct("Extensions")
+            .field("len", &self.values.len())
+            .finish();
+    }
+}
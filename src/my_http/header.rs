@@ -0,0 +1,385 @@
+use color_eyre::{eyre::eyre, Report};
+use std::{borrow::Cow, fmt::Display, str::FromStr};
+
+/// The name of a header field. Names compare case-insensitively but keep the spelling they were created with
+#[derive(Debug, Clone)]
+pub struct HeaderName(Cow<'static, str>);
+impl HeaderName {
+    pub const ACCEPT: HeaderName = HeaderName::from_static("Accept");
+    pub const ACCEPT_ENCODING: HeaderName = HeaderName::from_static("Accept-Encoding");
+    pub const ACCEPT_RANGES: HeaderName = HeaderName::from_static("Accept-Ranges");
+    pub const ALLOW: HeaderName = HeaderName::from_static("Allow");
+    pub const CACHE_CONTROL: HeaderName = HeaderName::from_static("Cache-Control");
+    pub const CONNECTION: HeaderName = HeaderName::from_static("Connection");
+    pub const CONTENT_ENCODING: HeaderName = HeaderName::from_static("Content-Encoding");
+    pub const CONTENT_LENGTH: HeaderName = HeaderName::from_static("Content-Length");
+    pub const CONTENT_RANGE: HeaderName = HeaderName::from_static("Content-Range");
+    pub const CONTENT_TYPE: HeaderName = HeaderName::from_static("Content-Type");
+    pub const DATE: HeaderName = HeaderName::from_static("Date");
+    pub const ETAG: HeaderName = HeaderName::from_static("ETag");
+    pub const HOST: HeaderName = HeaderName::from_static("Host");
+    pub const IF_MATCH: HeaderName = HeaderName::from_static("If-Match");
+    pub const IF_MODIFIED_SINCE: HeaderName = HeaderName::from_static("If-Modified-Since");
+    pub const IF_NONE_MATCH: HeaderName = HeaderName::from_static("If-None-Match");
+    pub const IF_RANGE: HeaderName = HeaderName::from_static("If-Range");
+    pub const IF_UNMODIFIED_SINCE: HeaderName = HeaderName::from_static("If-Unmodified-Since");
+    pub const KEEP_ALIVE: HeaderName = HeaderName::from_static("Keep-Alive");
+    pub const LAST_MODIFIED: HeaderName = HeaderName::from_static("Last-Modified");
+    pub const LOCATION: HeaderName = HeaderName::from_static("Location");
+    pub const RANGE: HeaderName = HeaderName::from_static("Range");
+    pub const RETRY_AFTER: HeaderName = HeaderName::from_static("Retry-After");
+    pub const SET_COOKIE: HeaderName = HeaderName::from_static("Set-Cookie");
+    pub const TRAILER: HeaderName = HeaderName::from_static("Trailer");
+    pub const TRANSFER_ENCODING: HeaderName = HeaderName::from_static("Transfer-Encoding");
+    pub const USER_AGENT: HeaderName = HeaderName::from_static("User-Agent");
+    pub const VARY: HeaderName = HeaderName::from_static("Vary");
+    pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("X-Request-Id");
+
+    pub const fn from_static(name: &'static str) -> Self {
+        return HeaderName(Cow::Borrowed(name));
+    }
+    /// A name that is rejected unless it is an RFC 9110 token.
+    /// Names made with `From` are not checked, [HeaderMap] leaves out the fields whose name is not a token when it writes them
+    pub fn new(name: impl Into<String>) -> Result<Self, Report> {
+        let name = name.into();
+        if !HeaderName::is_token(&name) {
+            return Err(eyre!(
+                "in HeaderName::new {:?} is not a valid field name",
+                name
+            ));
+        }
+        return Ok(HeaderName(Cow::Owned(name)));
+    }
+    /// Whether `name` is a non-empty run of the token characters, so it holds no whitespace, `:`, CR or LF
+    pub fn is_token(name: &str) -> bool {
+        return !name.is_empty()
+            && name
+                .bytes()
+                .all(|byte| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte));
+    }
+    pub fn as_str(&self) -> &str {
+        return &self.0;
+    }
+}
+impl PartialEq for HeaderName {
+    fn eq(&self, other: &Self) -> bool {
+        return self.0.eq_ignore_ascii_case(&other.0);
+    }
+}
+impl Eq for HeaderName {}
+impl PartialEq<str> for HeaderName {
+    fn eq(&self, other: &str) -> bool {
+        return self.0.eq_ignore_ascii_case(other);
+    }
+}
+impl PartialEq<&str> for HeaderName {
+    fn eq(&self, other: &&str) -> bool {
+        return self.0.eq_ignore_ascii_case(other);
+    }
+}
+impl From<&str> for HeaderName {
+    fn from(name: &str) -> Self {
+        return HeaderName(Cow::Owned(name.to_string()));
+    }
+}
+impl From<String> for HeaderName {
+    fn from(name: String) -> Self {
+        return HeaderName(Cow::Owned(name));
+    }
+}
+impl From<&HeaderName> for HeaderName {
+    fn from(name: &HeaderName) -> Self {
+        return name.clone();
+    }
+}
+impl AsRef<str> for HeaderName {
+    fn as_ref(&self) -> &str {
+        return self.as_str();
+    }
+}
+impl Display for HeaderName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", self.0);
+    }
+}
+
+/// The value of one header field.
+///
+/// A CR, LF or NUL would end the field early and let the rest of the value be read as another header,
+/// so [HeaderValue::new] rejects them and the `From` conversions replace each one with a space, as RFC 9110 section 5.5 allows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderValue(String);
+impl HeaderValue {
+    /// A value that is rejected if it holds a CR, LF or NUL
+    pub fn new(value: impl Into<String>) -> Result<Self, Report> {
+        let value = value.into();
+        if value.contains(HeaderValue::is_forbidden) {
+            return Err(eyre!(
+                "in HeaderValue::new {:?} contains a CR, LF or NUL",
+                value
+            ));
+        }
+        return Ok(HeaderValue(value));
+    }
+    /// Replaces every CR, LF and NUL in `value` with a space
+    fn sanitized(value: String) -> Self {
+        if !value.contains(HeaderValue::is_forbidden) {
+            return HeaderValue(value);
+        }
+        return HeaderValue(value.replace(HeaderValue::is_forbidden, " "));
+    }
+    fn is_forbidden(character: char) -> bool {
+        return matches!(character, '\r' | '\n' | '\0');
+    }
+    pub fn as_str(&self) -> &str {
+        return &self.0;
+    }
+    /// Parses the whole value, for example a `Content-Length` into a number
+    pub fn parse<T: FromStr>(&self) -> Option<T> {
+        return self.0.trim().parse().ok();
+    }
+    /// Splits a comma separated list like `gzip, br` into its trimmed, non-empty elements
+    pub fn split_list(&self) -> impl Iterator<Item = &str> {
+        return self
+            .0
+            .split(',')
+            .map(str::trim)
+            .filter(|element| !element.is_empty());
+    }
+}
+impl From<&str> for HeaderValue {
+    fn from(value: &str) -> Self {
+        return HeaderValue::sanitized(value.to_string());
+    }
+}
+impl From<String> for HeaderValue {
+    fn from(value: String) -> Self {
+        return HeaderValue::sanitized(value);
+    }
+}
+impl From<&String> for HeaderValue {
+    fn from(value: &String) -> Self {
+        return HeaderValue::sanitized(value.clone());
+    }
+}
+impl From<Cow<'_, str>> for HeaderValue {
+    fn from(value: Cow<'_, str>) -> Self {
+        return HeaderValue::sanitized(value.into_owned());
+    }
+}
+impl From<usize> for HeaderValue {
+    fn from(value: usize) -> Self {
+        return HeaderValue(value.to_string());
+    }
+}
+impl From<u64> for HeaderValue {
+    fn from(value: u64) -> Self {
+        return HeaderValue(value.to_string());
+    }
+}
+impl AsRef<str> for HeaderValue {
+    fn as_ref(&self) -> &str {
+        return self.as_str();
+    }
+}
+impl Display for HeaderValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", self.0);
+    }
+}
+
+/// Header fields in the order they were added.
+///
+/// A name may hold several values, like repeated `Set-Cookie` or `Accept` fields, and lookups ignore the case of the name
+#[derive(Debug, Clone, Default)]
+pub struct HeaderMap {
+    fields: Vec<(HeaderName, HeaderValue)>,
+}
+impl HeaderMap {
+    pub fn new() -> Self {
+        return HeaderMap::default();
+    }
+    /// The first value of `name`
+    pub fn get(&self, name: impl AsRef<str>) -> Option<&HeaderValue> {
+        return self.get_all(name).next();
+    }
+    /// Every value of `name` in the order they were added
+    pub fn get_all(&self, name: impl AsRef<str>) -> impl Iterator<Item = &HeaderValue> {
+        let name = name.as_ref().to_string();
+        return self
+            .fields
+            .iter()
+            .filter(move |(field_name, _)| *field_name == name.as_str())
+            .map(|(_, value)| value);
+    }
+    /// The elements of every comma separated value of `name`, so `Accept: a, b` and two `Accept` fields read the same
+    pub fn get_list(&self, name: impl AsRef<str>) -> impl Iterator<Item = &str> {
+        return self.get_all(name).flat_map(HeaderValue::split_list);
+    }
+    /// Whether the comma separated values of `name` contain `token`, ignoring case
+    pub fn contains_token(&self, name: impl AsRef<str>, token: &str) -> bool {
+        return self
+            .get_list(name)
+            .any(|element| element.eq_ignore_ascii_case(token));
+    }
+    pub fn contains(&self, name: impl AsRef<str>) -> bool {
+        return self.get(name).is_some();
+    }
+    /// Sets `name` to `value`, replacing every previous value. The field keeps the position of the first value it replaces
+    pub fn insert(&mut self, name: impl Into<HeaderName>, value: impl Into<HeaderValue>) {
+        let name = name.into();
+        let value = value.into();
+
+        match self
+            .fields
+            .iter()
+            .position(|(field_name, _)| *field_name == name)
+        {
+            Some(first) => {
+                self.fields[first].1 = value;
+                let mut index = 0;
+                self.fields.retain(|(field_name, _)| {
+                    let keep = index <= first || *field_name != name;
+                    index += 1;
+                    return keep;
+                });
+            }
+            None => self.fields.push((name, value)),
+        }
+    }
+    /// Adds another value for `name` after any existing ones
+    pub fn append(&mut self, name: impl Into<HeaderName>, value: impl Into<HeaderValue>) {
+        self.fields.push((name.into(), value.into()));
+    }
+    /// Removes every value of `name`, returning the first
+    pub fn remove(&mut self, name: impl AsRef<str>) -> Option<HeaderValue> {
+        let name = name.as_ref();
+        let mut removed = None;
+        self.fields.retain(|(field_name, value)| {
+            if *field_name != name {
+                return true;
+            }
+            if removed.is_none() {
+                removed = Some(value.clone());
+            }
+            return false;
+        });
+        return removed;
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&HeaderName, &HeaderValue)> {
+        return self.fields.iter().map(|(name, value)| (name, value));
+    }
+    /// The number of fields, counting every value of a repeated name
+    pub fn len(&self) -> usize {
+        return self.fields.len();
+    }
+    pub fn is_empty(&self) -> bool {
+        return self.fields.is_empty();
+    }
+
+    pub fn content_length(&self) -> Option<u64> {
+        return self.get(HeaderName::CONTENT_LENGTH)?.parse();
+    }
+    pub fn content_type(&self) -> Option<&str> {
+        return self.get(HeaderName::CONTENT_TYPE).map(HeaderValue::as_str);
+    }
+    pub fn host(&self) -> Option<&str> {
+        return self.get(HeaderName::HOST).map(HeaderValue::as_str);
+    }
+    pub fn user_agent(&self) -> Option<&str> {
+        return self.get(HeaderName::USER_AGENT).map(HeaderValue::as_str);
+    }
+}
+impl<'a> IntoIterator for &'a HeaderMap {
+    type Item = (&'a HeaderName, &'a HeaderValue);
+    type IntoIter = std::iter::Map<
+        std::slice::Iter<'a, (HeaderName, HeaderValue)>,
+        fn(&'a (HeaderName, HeaderValue)) -> (&'a HeaderName, &'a HeaderValue),
+    >;
+    fn into_iter(self) -> Self::IntoIter {
+        return self.fields.iter().map(|(name, value)| (name, value));
+    }
+}
+impl Display for HeaderMap {
+    /// Writes every field the way it goes on the wire, one `Name: value\r\n` line each.
+    /// A field whose name is not a token is left out, it could end the line early or smuggle in another field
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in self.iter() {
+            if !HeaderName::is_token(name.as_str()) {
+                continue;
+            }
+            write!(f, "{}: {}\r\n", name, value)?;
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_case_insensitive_multi_valued() {
+        let mut headers = HeaderMap::new();
+        headers.append("Set-Cookie", "a=1");
+        headers.append("content-type", "text/html");
+        headers.append("set-cookie", "b=2");
+        headers.append("Accept", "text/html, image/*");
+        headers.append("ACCEPT", "*/*");
+
+        assert_eq!(headers.content_type(), Some("text/html"));
+        assert_eq!(
+            headers
+                .get_all(HeaderName::SET_COOKIE)
+                .map(HeaderValue::as_str)
+                .collect::<Vec<_>>(),
+            ["a=1", "b=2"]
+        );
+        assert_eq!(
+            headers.get_list("accept").collect::<Vec<_>>(),
+            ["text/html", "image/*", "*/*"]
+        );
+    }
+
+    #[test]
+    fn test_insert_keeps_order() {
+        let mut headers = HeaderMap::new();
+        headers.append("A", "1");
+        headers.append("B", "2");
+        headers.append("a", "3");
+        headers.append("C", "4");
+
+        headers.insert("a", "5");
+        assert_eq!(headers.to_string(), "A: 5\r\nB: 2\r\nC: 4\r\n");
+
+        assert_eq!(headers.remove("b"), Some(HeaderValue::from("2")));
+        assert_eq!(headers.len(), 2);
+    }
+
+    #[test]
+    fn test_values_can_not_split_fields() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::LOCATION,
+            "/next\r\nSet-Cookie: session=stolen\0",
+        );
+        assert_eq!(
+            headers.to_string(),
+            "Location: /next  Set-Cookie: session=stolen \r\n"
+        );
+
+        assert!(HeaderValue::new("/next\r\nSet-Cookie: a=b").is_err());
+        assert!(HeaderValue::new("bytes */100").is_ok());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Evil: 1\r\nSet-Cookie", "a=b");
+        headers.insert("Content Type", "text/html");
+        headers.insert("X-Fine", "1");
+        assert_eq!(headers.to_string(), "X-Fine: 1\r\n");
+
+        assert!(HeaderName::new("X-Request-Id").is_ok());
+        for name in ["", "Host ", "Set-Cookie:", "X\r\nY"] {
+            assert!(HeaderName::new(name).is_err());
+        }
+    }
+}
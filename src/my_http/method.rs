@@ -1,7 +1,7 @@
 use color_eyre::{eyre::eyre, Report};
 use std::{fmt::Display, str::FromStr};
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Method {
     #[default]
     Get,
@@ -0,0 +1,278 @@
+use std::{borrow::Cow, collections::HashMap, path::Path};
+
+/// Media types by file extension, used when none of [MimeTypes::insert] apply
+const BUILT_IN: &[(&str, &str)] = &[
+    // text
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("txt", "text/plain"),
+    ("text", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("tsv", "text/tab-separated-values"),
+    ("xml", "text/xml"),
+    ("ics", "text/calendar"),
+    ("vtt", "text/vtt"),
+    // application
+    ("json", "application/json"),
+    ("map", "application/json"),
+    ("webmanifest", "application/manifest+json"),
+    ("jsonld", "application/ld+json"),
+    ("wasm", "application/wasm"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("br", "application/x-brotli"),
+    ("zst", "application/zstd"),
+    ("tar", "application/x-tar"),
+    ("bz2", "application/x-bzip2"),
+    ("xz", "application/x-xz"),
+    ("7z", "application/x-7z-compressed"),
+    ("rar", "application/vnd.rar"),
+    ("rss", "application/rss+xml"),
+    ("atom", "application/atom+xml"),
+    ("xhtml", "application/xhtml+xml"),
+    ("rtf", "application/rtf"),
+    ("doc", "application/msword"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    ("xls", "application/vnd.ms-excel"),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("odt", "application/vnd.oasis.opendocument.text"),
+    ("epub", "application/epub+zip"),
+    ("jar", "application/java-archive"),
+    ("bin", "application/octet-stream"),
+    ("exe", "application/octet-stream"),
+    ("iso", "application/octet-stream"),
+    // images
+    ("png", "image/png"),
+    ("apng", "image/apng"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("jfif", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("svg", "image/svg+xml"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("bmp", "image/bmp"),
+    ("tif", "image/tiff"),
+    ("tiff", "image/tiff"),
+    // audio
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("oga", "audio/ogg"),
+    ("opus", "audio/opus"),
+    ("wav", "audio/wav"),
+    ("weba", "audio/webm"),
+    ("flac", "audio/flac"),
+    ("aac", "audio/aac"),
+    ("m4a", "audio/mp4"),
+    ("mid", "audio/midi"),
+    ("midi", "audio/midi"),
+    // video
+    ("mp4", "video/mp4"),
+    ("m4v", "video/mp4"),
+    ("webm", "video/webm"),
+    ("ogv", "video/ogg"),
+    ("mov", "video/quicktime"),
+    ("avi", "video/x-msvideo"),
+    ("mkv", "video/x-matroska"),
+    ("mpeg", "video/mpeg"),
+    ("ts", "video/mp2t"),
+    // fonts
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("eot", "application/vnd.ms-fontobject"),
+];
+
+/// Maps file extensions to media types, with user added mappings taking precedence over the built in ones.
+///
+/// Text types get `; charset=utf-8` appended in [MimeTypes::content_type] since the files served are assumed to be utf-8
+#[derive(Debug, Clone, Default)]
+pub struct MimeTypes {
+    overrides: HashMap<String, String>,
+}
+impl MimeTypes {
+    /// How many bytes from the start of a file [MimeTypes::sniff] needs
+    pub const SNIFF_LEN: usize = 512;
+
+    pub fn new() -> Self {
+        return MimeTypes::default();
+    }
+    /// Maps `extension`, without the dot and ignoring case, to `media_type`, replacing any mapping it had
+    pub fn insert(&mut self, extension: &str, media_type: impl Into<String>) {
+        self.overrides.insert(
+            extension.trim_start_matches('.').to_lowercase(),
+            media_type.into(),
+        );
+    }
+    /// The media type of files ending in `extension`, like `image/png` for `png`
+    pub fn get(&self, extension: &str) -> Option<&str> {
+        let extension = extension.trim_start_matches('.').to_lowercase();
+        if let Some(media_type) = self.overrides.get(&extension) {
+            return Some(media_type);
+        }
+        return BUILT_IN
+            .iter()
+            .find(|(known_extension, _)| *known_extension == extension)
+            .map(|(_, media_type)| *media_type);
+    }
+    /// The media type for the extension of `path`
+    pub fn for_path(&self, path: &Path) -> Option<&str> {
+        return path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| self.get(extension));
+    }
+    /// The `Content-Type` value for `media_type`, with a charset parameter for text types that have none
+    pub fn content_type(media_type: &str) -> Cow<'_, str> {
+        let is_text = media_type
+            .split('/')
+            .next()
+            .is_some_and(|top_level| top_level.eq_ignore_ascii_case("text"));
+        return if is_text && !media_type.contains(';') {
+            Cow::from(format!("{}; charset=utf-8", media_type))
+        } else {
+            Cow::from(media_type)
+        };
+    }
+    /// Guesses a media type from the first bytes of a file, up to [MimeTypes::SNIFF_LEN] of them.
+    /// Falls back to `text/plain` for text and `application/octet-stream` for anything else
+    pub fn sniff(bytes: &[u8]) -> &'static str {
+        const SIGNATURES: &[(&[u8], &str)] = &[
+            (b"\x89PNG\r\n\x1A\n", "image/png"),
+            (b"GIF87a", "image/gif"),
+            (b"GIF89a", "image/gif"),
+            (b"\xFF\xD8\xFF", "image/jpeg"),
+            (b"\x00\x00\x01\x00", "image/vnd.microsoft.icon"),
+            (b"%PDF-", "application/pdf"),
+            (b"PK\x03\x04", "application/zip"),
+            (b"\x1F\x8B\x08", "application/gzip"),
+            (b"\x28\xB5\x2F\xFD", "application/zstd"),
+            (b"\x00asm", "application/wasm"),
+            (b"wOFF", "font/woff"),
+            (b"wOF2", "font/woff2"),
+            (b"OggS", "audio/ogg"),
+            (b"ID3", "audio/mpeg"),
+            (b"\x1A\x45\xDF\xA3", "video/webm"),
+        ];
+        let bytes = &bytes[..bytes.len().min(MimeTypes::SNIFF_LEN)];
+        if let Some((_, media_type)) = SIGNATURES
+            .iter()
+            .find(|(signature, _)| bytes.starts_with(signature))
+        {
+            return media_type;
+        }
+
+        if bytes.len() >= 12 && bytes.starts_with(b"RIFF") {
+            match &bytes[8..12] {
+                b"WEBP" => return "image/webp",
+                b"WAVE" => return "audio/wav",
+                b"AVI " => return "video/x-msvideo",
+                _ => {}
+            }
+        }
+        if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" {
+            return match &bytes[8..12] {
+                b"avif" => "image/avif",
+                b"qt  " => "video/quicktime",
+                _ => "video/mp4",
+            };
+        }
+
+        let text = bytes.trim_ascii_start().to_ascii_lowercase();
+        for (prefix, media_type) in [
+            (&b"<!doctype html"[..], "text/html"),
+            (b"<html", "text/html"),
+            (b"<svg", "image/svg+xml"),
+            (b"<?xml", "text/xml"),
+        ] {
+            if text.starts_with(prefix) {
+                return media_type;
+            }
+        }
+
+        // a multi byte character cut off at the end of the sniffed bytes is still text
+        let is_utf8 = match std::str::from_utf8(bytes) {
+            Ok(_) => true,
+            Err(utf8_error) => utf8_error.error_len().is_none(),
+        };
+        let is_binary = bytes
+            .iter()
+            .any(|&byte| byte < 0x20 && !b"\t\n\r\x0C".contains(&byte));
+        return if is_utf8 && !is_binary {
+            "text/plain"
+        } else {
+            "application/octet-stream"
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lookup_and_overrides() {
+        let mut mime_types = MimeTypes::new();
+        assert_eq!(mime_types.get("MP4"), Some("video/mp4"));
+        assert_eq!(
+            mime_types.for_path(Path::new("web/favicon.ico")),
+            Some("image/vnd.microsoft.icon")
+        );
+        assert_eq!(mime_types.get("unknown"), None);
+
+        mime_types.insert(".js", "application/javascript");
+        mime_types.insert("unknown", "application/x-unknown");
+        assert_eq!(mime_types.get("js"), Some("application/javascript"));
+        assert_eq!(mime_types.get("unknown"), Some("application/x-unknown"));
+
+        assert_eq!(
+            MimeTypes::content_type("text/css"),
+            "text/css; charset=utf-8"
+        );
+        assert_eq!(
+            MimeTypes::content_type("text/html; charset=latin1"),
+            "text/html; charset=latin1"
+        );
+        assert_eq!(MimeTypes::content_type("image/png"), "image/png");
+    }
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(
+            MimeTypes::sniff(b"\x89PNG\r\n\x1A\n\0\0\0\rIHDR"),
+            "image/png"
+        );
+        assert_eq!(
+            MimeTypes::sniff(b"\0\0\0\x20ftypisom\0\0\x02\0"),
+            "video/mp4"
+        );
+        assert_eq!(MimeTypes::sniff(b"RIFF\0\0\0\0WEBPVP8 "), "image/webp");
+        assert_eq!(MimeTypes::sniff(b"\n  <!DOCTYPE html><html>"), "text/html");
+        assert_eq!(
+            MimeTypes::sniff("quack quack \u{1F986}".as_bytes()),
+            "text/plain"
+        );
+        assert_eq!(
+            MimeTypes::sniff(b"\x01\x02\x03"),
+            "application/octet-stream"
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use std::fmt::Display;
+
+/// An inclusive span of bytes `start..=end` out of a representation, as asked for in a `Range` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+impl ByteRange {
+    pub fn len(&self) -> u64 {
+        return self.end - self.start + 1;
+    }
+    /// Never true, a range holds at least its first byte
+    pub fn is_empty(&self) -> bool {
+        return false;
+    }
+    /// The value of a `Content-Range` header for this range out of `complete_len` bytes
+    pub fn content_range(&self, complete_len: u64) -> String {
+        return format!("bytes {}-{}/{}", self.start, self.end, complete_len);
+    }
+}
+impl Display for ByteRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}-{}", self.start, self.end);
+    }
+}
+
+/// What a `Range` header asks of a representation that is `len` bytes long
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// The header can not be parsed, uses another unit or asks for too many ranges.
+    /// It is ignored and the whole representation is sent
+    Ignored,
+    /// The ranges to send in a `206 Partial Content`, sorted with overlapping ranges merged
+    Satisfiable(Vec<ByteRange>),
+    /// None of the ranges overlap the representation, answered with `416 Range Not Satisfiable`
+    Unsatisfiable,
+}
+impl RangeRequest {
+    /// More ranges than this in one header are ignored, so a request can't ask for the same bytes over and over
+    pub const MAX_RANGES: usize = 16;
+
+    /// Parses a `Range` header like `bytes=0-99, 200-, -500` against a representation `len` bytes long
+    pub fn parse(header: &str, len: u64) -> Self {
+        let specs = match header.trim().split_once('=') {
+            Some((unit, specs)) if unit.trim().eq_ignore_ascii_case("bytes") => specs,
+            _ => return RangeRequest::Ignored,
+        };
+
+        let mut ranges = Vec::new();
+        let mut spec_count = 0;
+        for spec in specs
+            .split(',')
+            .map(str::trim)
+            .filter(|spec| !spec.is_empty())
+        {
+            spec_count += 1;
+            if spec_count > RangeRequest::MAX_RANGES {
+                return RangeRequest::Ignored;
+            }
+            let (first, last) = match spec.split_once('-') {
+                Some(positions) => positions,
+                None => return RangeRequest::Ignored,
+            };
+            let range = match (first.trim(), last.trim()) {
+                ("", suffix_len) => match suffix_len.parse::<u64>() {
+                    Ok(0) => None,
+                    Ok(suffix_len) if len > 0 => Some(ByteRange {
+                        start: len.saturating_sub(suffix_len),
+                        end: len - 1,
+                    }),
+                    Ok(_) => None,
+                    Err(_) => return RangeRequest::Ignored,
+                },
+                (first, last) => {
+                    let Ok(start) = first.parse::<u64>() else {
+                        return RangeRequest::Ignored;
+                    };
+                    let end = match last {
+                        "" => u64::MAX,
+                        last => match last.parse::<u64>() {
+                            Ok(end) if end >= start => end,
+                            _ => return RangeRequest::Ignored,
+                        },
+                    };
+                    (start < len).then(|| ByteRange {
+                        start,
+                        end: end.min(len - 1),
+                    })
+                }
+            };
+            ranges.extend(range);
+        }
+
+        if spec_count == 0 {
+            return RangeRequest::Ignored;
+        }
+        if ranges.is_empty() {
+            return RangeRequest::Unsatisfiable;
+        }
+
+        ranges.sort_by_key(|range| range.start);
+        let mut merged: Vec<ByteRange> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(previous) if range.start <= previous.end.saturating_add(1) => {
+                    previous.end = previous.end.max(range.end);
+                }
+                _ => merged.push(range),
+            }
+        }
+        return RangeRequest::Satisfiable(merged);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ranges(pairs: &[(u64, u64)]) -> RangeRequest {
+        return RangeRequest::Satisfiable(
+            pairs
+                .iter()
+                .map(|&(start, end)| ByteRange { start, end })
+                .collect(),
+        );
+    }
+
+    #[test]
+    fn test_parse_ranges() {
+        assert_eq!(RangeRequest::parse("bytes=0-99", 1000), ranges(&[(0, 99)]));
+        assert_eq!(
+            RangeRequest::parse("bytes=900-", 1000),
+            ranges(&[(900, 999)])
+        );
+        assert_eq!(
+            RangeRequest::parse("bytes=-100", 1000),
+            ranges(&[(900, 999)])
+        );
+        assert_eq!(
+            RangeRequest::parse("bytes=-5000", 1000),
+            ranges(&[(0, 999)])
+        );
+        assert_eq!(
+            RangeRequest::parse("bytes=990-2000", 1000),
+            ranges(&[(990, 999)])
+        );
+        assert_eq!(
+            RangeRequest::parse("bytes=500-599, 0-9, 5-20, 2000-", 1000),
+            ranges(&[(0, 20), (500, 599)])
+        );
+
+        assert_eq!(
+            RangeRequest::parse("bytes=1000-", 1000),
+            RangeRequest::Unsatisfiable
+        );
+        assert_eq!(
+            RangeRequest::parse("bytes=-0", 1000),
+            RangeRequest::Unsatisfiable
+        );
+        assert_eq!(
+            RangeRequest::parse("bytes=0-", 0),
+            RangeRequest::Unsatisfiable
+        );
+
+        assert_eq!(
+            RangeRequest::parse("items=0-1", 1000),
+            RangeRequest::Ignored
+        );
+        assert_eq!(
+            RangeRequest::parse("bytes=5-1", 1000),
+            RangeRequest::Ignored
+        );
+        assert_eq!(
+            RangeRequest::parse("bytes=a-b", 1000),
+            RangeRequest::Ignored
+        );
+        assert_eq!(RangeRequest::parse("bytes=", 1000), RangeRequest::Ignored);
+        let many = format!("bytes={}", vec!["0-0"; 17].join(","));
+        assert_eq!(RangeRequest::parse(&many, 1000), RangeRequest::Ignored);
+    }
+}
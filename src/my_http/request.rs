@@ -1,10 +1,13 @@
-use crate::my_http::{method::Method, Version};
+mod chunked;
+
+use crate::my_http::{
+    method::Method, Extensions, HeaderMap, HeaderName, HttpError, StatusCode, Version,
+};
 
 use color_eyre::{eyre::eyre, Report};
 use std::{
-    collections::HashMap,
-    io::{BufRead, BufReader},
-    net::TcpStream,
+    io::{BufRead, Read},
+    net::SocketAddr,
     str::FromStr,
 };
 
@@ -13,11 +16,23 @@ pub struct Request {
     method: Method,
     path: String,
     version: Version,
-    headers: HashMap<String, String>,
-    body: String,
+    headers: HeaderMap,
+    body: Vec<u8>,
+    /// Fields sent after a chunked body, kept apart so they can never stand in for headers
+    trailers: HeaderMap,
+    peer_addr: Option<SocketAddr>,
+    local_addr: Option<SocketAddr>,
+    extensions: Extensions,
+    /// Captured by a route pattern like `/users/:id`
+    params: Vec<(String, String)>,
 }
 impl Request {
     pub const MIN_BYTES_READ: usize = 2;
+    /// The largest request line and header block [Request::read_from] will buffer,
+    /// a longer head is rejected with [StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE]
+    pub const MAX_HEAD_SIZE: usize = 64 * 1024;
+    /// The largest body [Request::from_str] will accept
+    pub const DEFAULT_MAX_BODY_SIZE: usize = 8 * 1024 * 1024;
     pub fn version(&self) -> Version {
         return self.version;
     }
@@ -27,38 +42,230 @@ impl Request {
     pub fn path(&self) -> &str {
         return &self.path;
     }
+    /// The value a route pattern captured for `name`, `id` for `/users/:id` or `rest` for `/assets/*rest`
+    pub fn param(&self, name: &str) -> Option<&str> {
+        return self
+            .params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_str());
+    }
+    /// Every parameter captured by the matched route pattern, in pattern order
+    pub fn params(&self) -> &[(String, String)] {
+        return &self.params;
+    }
+    /// The first value of the header `name`, ignoring case
+    pub fn header(&self, name: impl AsRef<str>) -> Option<&str> {
+        return self.headers.get(name).map(|value| value.as_str());
+    }
+    pub fn headers(&self) -> &HeaderMap {
+        return &self.headers;
+    }
+    /// The raw bytes of the body, empty when the request had none
+    pub fn body(&self) -> &[u8] {
+        return &self.body;
+    }
+    /// The trailer fields sent after a chunked body, empty for any other body
+    pub fn trailers(&self) -> &HeaderMap {
+        return &self.trailers;
+    }
+    /// The address of the client, [None] when the request was not read off a connection
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        return self.peer_addr;
+    }
+    /// The address of the server socket the request arrived on
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        return self.local_addr;
+    }
+    /// Data attached to this request by middleware, see [Extensions]
+    pub fn extensions(&self) -> &Extensions {
+        return &self.extensions;
+    }
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        return &mut self.extensions;
+    }
+    pub(crate) fn set_params(&mut self, params: Vec<(String, String)>) {
+        self.params = params;
+    }
+    /// Records the addresses of the connection the request was read from
+    pub(crate) fn set_addresses(
+        &mut self,
+        peer_addr: Option<SocketAddr>,
+        local_addr: Option<SocketAddr>,
+    ) {
+        self.peer_addr = peer_addr;
+        self.local_addr = local_addr;
+    }
 }
-impl TryFrom<BufReader<TcpStream>> for Request {
-    type Error = Report;
-    /// Consume a [BufReader]<[TcpStream]> trying to read a line into a request
-    fn try_from(mut reader: BufReader<TcpStream>) -> Result<Self, Self::Error> {
+impl Request {
+    /// Reads the next request off `reader` without consuming it, so the same reader can serve a persistent connection.
+    ///
+    /// Returns [None] when the peer closed the connection before sending another request.
+    /// A body longer than `max_body_size` is rejected with [StatusCode::PAYLOAD_TOO_LARGE]
+    /// and a body shorter than its `Content-Length` with [StatusCode::BAD_REQUEST], and so is a connection that
+    /// ends partway through the head. A head longer than [Request::MAX_HEAD_SIZE] is a [StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE].
+    pub fn read_from<R: BufRead>(
+        reader: &mut R,
+        max_body_size: usize,
+    ) -> Result<Option<Self>, Report> {
         let mut input = String::new();
 
-        while reader.read_line(&mut input)? > Request::MIN_BYTES_READ {}
+        loop {
+            // the head is read through a limit so a client can not make the server buffer an endless line
+            let limit = Request::MAX_HEAD_SIZE.saturating_sub(input.len()) as u64;
+            let bytes_read = reader.by_ref().take(limit).read_line(&mut input)?;
+            // a head that stops without its line ending either hit the limit or the connection closed
+            if bytes_read == 0 || !input.ends_with('\n') {
+                if input.len() >= Request::MAX_HEAD_SIZE {
+                    Err(HttpError::new(
+                        StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+                        format!(
+                            "request head is larger than the limit of {} bytes",
+                            Request::MAX_HEAD_SIZE
+                        ),
+                    ))?;
+                }
+                if input.trim().is_empty() {
+                    return Ok(None);
+                }
+                Err(HttpError::new(
+                    StatusCode::BAD_REQUEST,
+                    "connection closed before the end of the request head",
+                ))?;
+            }
+            if bytes_read <= Request::MIN_BYTES_READ {
+                // blank lines before the request line are allowed and ignored
+                if input.trim().is_empty() {
+                    input.clear();
+                    continue;
+                }
+                break;
+            }
+        }
+
+        let mut request = Request::parse_head(&input)?;
+        request.body = request.read_body(reader, max_body_size)?;
 
-        return input.parse();
+        return Ok(Some(request));
     }
-}
-impl FromStr for Request {
-    type Err = Report;
+    /// Whether the client wants the connection kept open after this request.
+    ///
+    /// HTTP/1.1 connections are persistent unless the client sends `Connection: close`,
+    /// HTTP/1.0 connections close unless the client sends `Connection: keep-alive`.
+    pub fn keep_alive(&self) -> bool {
+        let has_token = |token| self.headers.contains_token(HeaderName::CONNECTION, token);
 
-    /// This function parses a raw request [String] into a [Request]
-    fn from_str(raw_request: &str) -> Result<Self, Self::Err> {
-        let mut lines = raw_request.lines();
+        return match self.version {
+            _ if has_token("close") => false,
+            Version::Http0_9 | Version::Http1_0 => has_token("keep-alive"),
+            Version::Http1_1 | Version::Http2 | Version::Http3 => true,
+        };
+    }
+
+    /// Reads the body following the header block, either chunked or exactly `Content-Length` bytes.
+    /// Neither header means no body, both headers is a [StatusCode::BAD_REQUEST].
+    /// Trailer fields of a chunked body go into [Request::trailers], never into the headers (RFC 9112 section 7.1.2)
+    fn read_body<R: BufRead>(
+        &mut self,
+        reader: &mut R,
+        max_body_size: usize,
+    ) -> Result<Vec<u8>, Report> {
+        if self.headers.contains(HeaderName::TRANSFER_ENCODING) {
+            if self.headers.contains(HeaderName::CONTENT_LENGTH) {
+                Err(HttpError::new(
+                    StatusCode::BAD_REQUEST,
+                    "request has both Content-Length and Transfer-Encoding",
+                ))?;
+            }
+            let codings = self
+                .headers
+                .get_list(HeaderName::TRANSFER_ENCODING)
+                .collect::<Vec<_>>();
+            if !matches!(codings.as_slice(), [coding] if coding.eq_ignore_ascii_case("chunked")) {
+                Err(HttpError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("Transfer-Encoding {} is not supported", codings.join(", ")),
+                ))?;
+            }
+
+            let (body, trailers) = chunked::decode(reader, max_body_size)?;
+            for (name, value) in trailers {
+                self.trailers.append(name, value);
+            }
+            return Ok(body);
+        }
+
+        let content_length = match self.content_length()? {
+            Some(content_length) => content_length,
+            None => return Ok(Vec::new()),
+        };
+
+        if content_length > max_body_size {
+            Err(HttpError::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!(
+                    "Content-Length {} is larger than the limit of {} bytes",
+                    content_length, max_body_size
+                ),
+            ))?;
+        }
+
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).map_err(|io_error| {
+            HttpError::new(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "body ended before Content-Length {} was reached\n{}",
+                    content_length, io_error
+                ),
+            )
+        })?;
+
+        return Ok(body);
+    }
+    /// A `Content-Length` that repeats the same value (`5, 5` or two fields) is accepted,
+    /// anything else that is not a single number is a [StatusCode::BAD_REQUEST]
+    fn content_length(&self) -> Result<Option<usize>, Report> {
+        if !self.headers.contains(HeaderName::CONTENT_LENGTH) {
+            return Ok(None);
+        }
+
+        let values = self
+            .headers
+            .get_list(HeaderName::CONTENT_LENGTH)
+            .collect::<Vec<_>>();
+        let mut lengths = values.iter().map(|length| length.parse::<usize>());
+        return match lengths.next() {
+            Some(Ok(length))
+                if lengths.all(|other| matches!(other, Ok(other) if other == length)) =>
+            {
+                Ok(Some(length))
+            }
+            _ => Err(HttpError::new(
+                StatusCode::BAD_REQUEST,
+                format!("{} is not a valid Content-Length", values.join(", ")),
+            ))?,
+        };
+    }
+}
+impl Request {
+    /// Parses the request line and headers. The body is left empty for [Request::read_from] to fill in
+    fn parse_head(raw_head: &str) -> Result<Self, Report> {
+        let mut lines = raw_head.lines();
 
         // parse the first line
         let mut first_line_words = lines
             .next()
-            .ok_or(eyre!("in Request::from_str raw_request is empty"))?
+            .ok_or(eyre!("in Request::parse_head raw_head is empty"))?
             .split_whitespace();
 
         let method = first_line_words
             .next()
-            .ok_or(eyre!("in Request::from_str first_line is missing method"))?
+            .ok_or(eyre!("in Request::parse_head first_line is missing method"))?
             .parse()
             .map_err(|parse_error| {
                 eyre!(
-                    "in Request::from_str could not parse method in first_line of raw_request\n{}",
+                    "in Request::parse_head could not parse method in first_line of raw_head\n{}",
                     parse_error
                 )
             })?;
@@ -66,20 +273,20 @@ impl FromStr for Request {
         let path = first_line_words
             .next()
             .ok_or(eyre!(
-                "in Request::from_str first_line of raw_request is missing a path"
+                "in Request::parse_head first_line of raw_head is missing a path"
             ))?
             .to_string();
 
         let version = first_line_words
             .next()
-            .ok_or(eyre!("in Request::from_str first_line of raw_request is missing a HTTP Version"))?
+            .ok_or(eyre!("in Request::parse_head first_line of raw_head is missing a HTTP Version"))?
             .parse().map_err(|parse_error| {
-                eyre!("in Request::from_str HTTP Version could not be parsed from first_line of raw_request\n{}", parse_error)
+                eyre!("in Request::parse_head HTTP Version could not be parsed from first_line of raw_head\n{}", parse_error)
             })?;
 
         // parse headers
-        let mut headers = HashMap::new();
-        while let Some(line) = lines.next() {
+        let mut headers = HeaderMap::new();
+        for line in lines.by_ref() {
             if line.is_empty() {
                 break;
             }
@@ -88,20 +295,180 @@ impl FromStr for Request {
                 .find(':')
                 .ok_or(eyre!("{} is not a valid header", line))?;
             let (key, value) = line.split_at(colon_index);
-            headers
-                .entry(key.to_string())
-                .or_insert(value.chars().skip(2).collect());
+            // whitespace before the colon lets two servers disagree on the field name, RFC 9112 section 5.1 requires a 400
+            if !HeaderName::is_token(key) {
+                Err(HttpError::new(
+                    StatusCode::BAD_REQUEST,
+                    format!("{:?} is not a valid header name", key),
+                ))?;
+            }
+            headers.append(key, value[1..].trim());
         }
 
-        // body
-        let body = lines.collect::<String>();
-
         return Ok(Request {
             method,
             path,
             version,
             headers,
-            body,
+            body: Vec::new(),
+            trailers: HeaderMap::new(),
+            peer_addr: None,
+            local_addr: None,
+            extensions: Extensions::new(),
+            params: Vec::new(),
         });
     }
 }
+impl FromStr for Request {
+    type Err = Report;
+
+    /// This function parses a complete raw request [String], including a body described by `Content-Length`, into a [Request]
+    fn from_str(raw_request: &str) -> Result<Self, Self::Err> {
+        return Request::read_from(&mut raw_request.as_bytes(), Request::DEFAULT_MAX_BODY_SIZE)?
+            .ok_or(eyre!("in Request::from_str raw_request is empty"));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_from_reuses_reader() {
+        let mut raw: &[u8] = b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\nGET /index.html HTTP/1.0\r\nconnection: Keep-Alive\r\n\r\n";
+
+        let first = Request::read_from(&mut raw, Request::DEFAULT_MAX_BODY_SIZE)
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.path(), "/");
+        assert!(first.keep_alive());
+
+        let second = Request::read_from(&mut raw, Request::DEFAULT_MAX_BODY_SIZE)
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.path(), "/index.html");
+        assert!(second.keep_alive());
+
+        assert!(Request::read_from(&mut raw, Request::DEFAULT_MAX_BODY_SIZE)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_keep_alive_defaults() {
+        let close: Request = "GET / HTTP/1.1\r\nConnection: close\r\n\r\n"
+            .parse()
+            .unwrap();
+        assert!(!close.keep_alive());
+
+        let http_1_0: Request = "GET / HTTP/1.0\r\n\r\n".parse().unwrap();
+        assert!(!http_1_0.keep_alive());
+    }
+
+    #[test]
+    fn test_body_is_read_by_content_length() {
+        let mut raw: &[u8] =
+            b"POST /upload HTTP/1.1\r\nContent-Length: 5\r\n\r\n\x00\xffab\nGET / HTTP/1.1\r\n\r\n";
+
+        let post = Request::read_from(&mut raw, 16).unwrap().unwrap();
+        assert_eq!(post.body, b"\x00\xffab\n");
+
+        let get = Request::read_from(&mut raw, 16).unwrap().unwrap();
+        assert!(get.body.is_empty());
+    }
+
+    #[test]
+    fn test_extensions_are_keyed_by_type() {
+        #[derive(Debug, PartialEq)]
+        struct UserId(u32);
+
+        let mut request: Request = "GET / HTTP/1.1\r\n\r\n".parse().unwrap();
+        assert_eq!(request.extensions_mut().insert(UserId(1)), None);
+        assert_eq!(request.extensions_mut().insert(UserId(2)), Some(UserId(1)));
+        request.extensions_mut().insert("a str");
+
+        assert_eq!(request.extensions().get::<UserId>(), Some(&UserId(2)));
+        assert_eq!(request.extensions().get::<&str>(), Some(&"a str"));
+        assert_eq!(request.extensions().get::<u32>(), None);
+    }
+
+    #[test]
+    fn test_chunked_body() {
+        let request: Request = "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\nDigest: x\r\nContent-Length: 99\r\nHost: evil\r\n\r\n"
+            .parse()
+            .unwrap();
+
+        assert_eq!(request.body, b"abc");
+        assert_eq!(request.trailers().get("digest"), Some(&"x".into()));
+        // trailers never stand in for headers the client did not send up front
+        assert_eq!(request.header("digest"), None);
+        assert_eq!(request.header("content-length"), None);
+        assert_eq!(request.header("host"), None);
+        assert_eq!(request.trailers().get("host"), Some(&"evil".into()));
+    }
+
+    #[test]
+    fn test_bad_bodies_have_status_codes() {
+        let status = |raw: &str, max_body_size| {
+            Request::read_from(&mut raw.as_bytes(), max_body_size)
+                .unwrap_err()
+                .downcast_ref::<HttpError>()
+                .map(HttpError::status)
+        };
+
+        assert_eq!(
+            status("POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort", 16),
+            Some(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(
+            status("POST / HTTP/1.1\r\nContent-Length: 17\r\n\r\n", 16),
+            Some(StatusCode::PAYLOAD_TOO_LARGE)
+        );
+        assert_eq!(
+            status("POST / HTTP/1.1\r\nContent-Length: 1, 2\r\n\r\nab", 16),
+            Some(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(
+            status(
+                "POST / HTTP/1.1\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\n\r\n",
+                16
+            ),
+            Some(StatusCode::BAD_REQUEST)
+        );
+    }
+
+    #[test]
+    fn test_bad_heads_have_status_codes() {
+        let status = |raw: &[u8]| {
+            Request::read_from(&mut &raw[..], Request::DEFAULT_MAX_BODY_SIZE)
+                .unwrap_err()
+                .downcast_ref::<HttpError>()
+                .map(HttpError::status)
+        };
+
+        assert_eq!(
+            status(b"GET / HTTP/1.1\r\nHost: local"),
+            Some(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(
+            status(b"GET / HTTP/1.1\r\nHost: localhost\r\n"),
+            Some(StatusCode::BAD_REQUEST)
+        );
+
+        assert_eq!(
+            status(b"POST / HTTP/1.1\r\nTransfer-Encoding : chunked\r\n\r\n"),
+            Some(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(
+            status(b"GET / HTTP/1.1\r\n Host: localhost\r\n\r\n"),
+            Some(StatusCode::BAD_REQUEST)
+        );
+
+        let mut endless = b"GET / HTTP/1.1\r\nX-Endless: ".to_vec();
+        endless.resize(Request::MAX_HEAD_SIZE * 2, b'a');
+        assert_eq!(
+            status(&endless),
+            Some(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE)
+        );
+    }
+}
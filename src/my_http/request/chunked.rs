@@ -0,0 +1,156 @@
+use crate::my_http::{HeaderName, HttpError, Request, StatusCode};
+
+use color_eyre::Report;
+use std::io::{BufRead, Read};
+
+/// The longest chunk-size or trailer line accepted, so a client can not make the server buffer an endless line
+const MAX_LINE_LENGTH: u64 = 8 * 1024;
+
+/// The header fields sent after the last chunk, in the order they were received
+pub type Trailers = Vec<(String, String)>;
+
+/// Decodes a `Transfer-Encoding: chunked` body from `reader`.
+///
+/// Chunk extensions are read and ignored. Returns the decoded data along with any trailer fields sent after the last chunk.
+/// A body that grows past `max_body_size` is a [StatusCode::PAYLOAD_TOO_LARGE], trailers longer than [Request::MAX_HEAD_SIZE]
+/// together are a [StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE] and anything malformed is a [StatusCode::BAD_REQUEST]
+pub fn decode<R: BufRead>(
+    reader: &mut R,
+    max_body_size: usize,
+) -> Result<(Vec<u8>, Trailers), Report> {
+    let mut body = Vec::new();
+
+    loop {
+        let line = read_line(reader)?;
+        // chunk-size [ ; chunk-ext ]
+        let size = line.split(';').next().unwrap_or_default().trim();
+        // from_str_radix would also take a leading `+`
+        let size = Some(size)
+            .filter(|size| !size.is_empty() && size.bytes().all(|byte| byte.is_ascii_hexdigit()))
+            .and_then(|size| usize::from_str_radix(size, 16).ok())
+            .ok_or_else(|| bad_request(format!("{} is not a valid chunk size", size)))?;
+
+        if size == 0 {
+            break;
+        }
+        if body.len().saturating_add(size) > max_body_size {
+            Err(HttpError::new(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!(
+                    "chunked body is larger than the limit of {} bytes",
+                    max_body_size
+                ),
+            ))?;
+        }
+
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader
+            .read_exact(&mut body[start..])
+            .map_err(|io_error| bad_request(format!("chunk ended early\n{}", io_error)))?;
+
+        if !read_line(reader)?.is_empty() {
+            Err(bad_request("chunk data is not followed by CRLF"))?;
+        }
+    }
+
+    let mut trailers = Vec::new();
+    let mut trailers_size = 0;
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        // with the CRLF that read_line stripped
+        trailers_size += line.len() + 2;
+        if trailers_size > Request::MAX_HEAD_SIZE {
+            Err(HttpError::new(
+                StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+                format!(
+                    "trailers are larger than the limit of {} bytes",
+                    Request::MAX_HEAD_SIZE
+                ),
+            ))?;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .filter(|(name, _)| HeaderName::is_token(name))
+            .ok_or_else(|| bad_request(format!("{} is not a valid trailer field", line)))?;
+        trailers.push((name.to_string(), value.trim().to_string()));
+    }
+
+    return Ok((body, trailers));
+}
+
+/// Reads one line and strips its line ending. The connection ending mid-body is a [StatusCode::BAD_REQUEST]
+fn read_line<R: BufRead>(reader: &mut R) -> Result<String, Report> {
+    let mut line = String::new();
+    let bytes_read = reader
+        .take(MAX_LINE_LENGTH)
+        .read_line(&mut line)
+        .map_err(|io_error| bad_request(format!("could not read chunk line\n{}", io_error)))?;
+
+    if bytes_read == 0 {
+        Err(bad_request("chunked body ended before the last chunk"))?;
+    }
+    if !line.ends_with('\n') {
+        Err(bad_request("chunk line is too long"))?;
+    }
+
+    return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+}
+
+fn bad_request(message: impl Into<String>) -> HttpError {
+    return HttpError::new(StatusCode::BAD_REQUEST, message);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_with_extensions_and_trailers() {
+        let mut raw: &[u8] =
+            b"4;name=value\r\nWiki\r\n6\r\npedia \r\nE\r\nin \r\n\r\nchunks.\r\n0\r\nExpires: never\r\n\r\nrest";
+
+        let (body, trailers) = decode(&mut raw, 1024).unwrap();
+
+        assert_eq!(body, b"Wikipedia in \r\n\r\nchunks.");
+        assert_eq!(trailers, [("Expires".to_string(), "never".to_string())]);
+        assert_eq!(raw, b"rest");
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let status = |raw: &[u8], max_body_size| {
+            decode(&mut &raw[..], max_body_size)
+                .unwrap_err()
+                .downcast_ref::<HttpError>()
+                .map(HttpError::status)
+        };
+
+        assert_eq!(
+            status(b"5\r\nhello\r\n0\r\n\r\n", 4),
+            Some(StatusCode::PAYLOAD_TOO_LARGE)
+        );
+        assert_eq!(
+            status(b"zz\r\nhello\r\n0\r\n\r\n", 16),
+            Some(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(status(b"5\r\nhel", 16), Some(StatusCode::BAD_REQUEST));
+        assert_eq!(
+            status(b"+5\r\nhello\r\n0\r\n\r\n", 16),
+            Some(StatusCode::BAD_REQUEST)
+        );
+
+        let mut endless_trailers = b"0\r\n".to_vec();
+        while endless_trailers.len() <= Request::MAX_HEAD_SIZE {
+            endless_trailers
+                .extend_from_slice(format!("X-Pad: {}\r\n", "a".repeat(1000)).as_bytes());
+        }
+        assert_eq!(
+            status(&endless_trailers, 16),
+            Some(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE)
+        );
+    }
+}
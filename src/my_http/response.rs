@@ -1,77 +1,202 @@
+mod builder;
 mod status;
 
-use crate::{
-    my_http::{body::Body, request::Request, response::status::StatusCode, Version},
-    SERVE_DIR,
-};
-
-use color_eyre::{owo_colors::OwoColorize, Report};
-use std::{
-    borrow::Cow,
-    collections::HashMap,
-    fmt::Display,
-    fs::File,
-    io::{Read, Write},
-    path::{Path, PathBuf},
-};
+pub use self::{builder::ResponseBuilder, status::StatusCode};
+
+use crate::my_http::{body::Body, HeaderMap, HeaderName, HeaderValue, Version};
+
+use color_eyre::owo_colors::OwoColorize;
+use std::{fmt::Display, io::Write};
 
 #[derive(Debug)]
 pub struct Response {
     version: Version,
-    headers: HashMap<String, String>,
+    headers: HeaderMap,
     body: Body,
     status: StatusCode,
+    /// Fields sent after the last chunk of a [Body::Stream]
+    trailers: HeaderMap,
 }
 impl Response {
+    pub fn builder() -> ResponseBuilder {
+        return ResponseBuilder::default();
+    }
+    /// A response with no body, used to answer requests that could not be served
+    pub fn empty(status: StatusCode) -> Self {
+        return Response::builder().status(status).empty();
+    }
+    /// A `200 OK` plain text response
+    pub fn text(text: impl Into<String>) -> Self {
+        return Response::builder()
+            .header(HeaderName::CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(text.into());
+    }
+    /// A `200 OK` html response
+    pub fn html(html: impl Into<String>) -> Self {
+        return Response::builder()
+            .header(HeaderName::CONTENT_TYPE, "text/html; charset=utf-8")
+            .body(html.into());
+    }
+    /// A `200 OK` response for an already serialized json document
+    pub fn json(json: impl Into<String>) -> Self {
+        return Response::builder()
+            .header(HeaderName::CONTENT_TYPE, "application/json")
+            .body(json.into());
+    }
+    /// Sends the client to `location`. `status` should be one of the 3xx codes, like [StatusCode::FOUND] or [StatusCode::MOVED_PERMANENTLY]
+    pub fn redirect(status: StatusCode, location: &str) -> Self {
+        return Response::builder()
+            .status(status)
+            .header(HeaderName::LOCATION, location)
+            .empty();
+    }
+    pub fn status(&self) -> StatusCode {
+        return self.status;
+    }
+    pub fn set_status(&mut self, status: StatusCode) {
+        self.status = status;
+    }
+    pub fn version(&self) -> Version {
+        return self.version;
+    }
+    /// The version should match the request, it decides how a [Body::Stream] is framed
+    pub fn set_version(&mut self, version: Version) {
+        self.version = version;
+    }
     pub fn start_line(&self) -> String {
         return format!("{} {}", self.version, self.status);
     }
-    pub fn headers(&self) -> String {
-        let mut headers = String::new();
-        for (key, value) in self.headers.iter() {
-            headers.push_str(format!("{}: {}\r\n", key, value).as_str());
-        }
-        return headers;
+    pub fn headers(&self) -> &HeaderMap {
+        return &self.headers;
+    }
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        return &mut self.headers;
     }
-    /// returns the number of bytes in `body`
     pub fn body(&self) -> &Body {
         return &self.body;
     }
-    /// Writes `self` in proper http format to `writer`
-    pub fn send(&self, writer: &mut dyn Write) -> Result<(), std::io::Error> {
-        writer.write(self.start_line().as_bytes())?;
-        writer.write(b"\r\n")?;
-        writer.write(self.headers().as_bytes())?;
-        writer.write(b"\r\n")?;
-        writer.write(b"\r\n")?;
-        writer.write(self.body.as_ref())?;
-        writer.write(b"\r\n")?;
+    /// Sets the header `name` to `value`, replacing any previous values
+    pub fn set_header(&mut self, name: impl Into<HeaderName>, value: impl Into<HeaderValue>) {
+        self.headers.insert(name, value);
+    }
+    /// Adds another value for the header `name`, for fields like `Set-Cookie` that may repeat
+    pub fn append_header(&mut self, name: impl Into<HeaderName>, value: impl Into<HeaderValue>) {
+        self.headers.append(name, value);
+    }
+    /// Replaces the body, keeping `Content-Length` in step with it.
+    /// A [Body::Stream] has no `Content-Length`, its framing is chosen in [Response::send].
+    /// Neither does a `1xx` or `204 No Content` response, which never has a body
+    pub fn set_body(&mut self, body: Body) {
+        match body.len() {
+            Some(len) if !self.forbids_body() => self.set_header(HeaderName::CONTENT_LENGTH, len),
+            _ => {
+                self.headers.remove(HeaderName::CONTENT_LENGTH);
+            }
+        }
+        self.body = body;
+    }
+    /// Sets a trailer field to send after the last chunk of a [Body::Stream].
+    /// Trailers are dropped when the body is not chunked
+    pub fn set_trailer(&mut self, name: impl Into<HeaderName>, value: impl Into<HeaderValue>) {
+        self.trailers.insert(name, value);
+    }
+    /// Drops the body but keeps the `Content-Length` or `Transfer-Encoding` it would have been sent with,
+    /// which is how a `HEAD` request is answered
+    pub fn clear_body(&mut self) {
+        self.set_framing_headers();
+        self.body = Body::from(Vec::new());
+    }
+    /// Whether the end of the body is marked by closing the connection.
+    /// That is the case for a [Body::Stream] sent to a client older than HTTP/1.1, which can not decode chunks
+    pub fn is_close_delimited(&self) -> bool {
+        return self.body.is_stream() && self.version < Version::Http1_1 && !self.forbids_body();
+    }
+    /// Writes `self` in proper http format to `writer`, consuming a [Body::Stream].
+    /// Nothing is written after the body so the next response on a persistent connection starts cleanly
+    pub fn send<W: Write>(&mut self, writer: &mut W) -> Result<(), std::io::Error> {
+        self.set_framing_headers();
+
+        writer.write_all(self.start_line().as_bytes())?;
+        writer.write_all(b"\r\n")?;
+        write!(writer, "{}", self.headers)?;
+        writer.write_all(b"\r\n")?;
+
+        if self.forbids_body() {
+            return Ok(());
+        }
+        let chunked = !self.is_close_delimited();
+        match &mut self.body {
+            Body::Stream(chunks) if chunked => {
+                for chunk in chunks {
+                    let chunk = chunk?;
+                    // an empty chunk would end the body early
+                    if chunk.is_empty() {
+                        continue;
+                    }
+                    write!(writer, "{:X}\r\n", chunk.len())?;
+                    writer.write_all(&chunk)?;
+                    writer.write_all(b"\r\n")?;
+                }
+                write!(writer, "0\r\n{}\r\n", self.trailers)?;
+            }
+            Body::Stream(chunks) => {
+                for chunk in chunks {
+                    writer.write_all(&chunk?)?;
+                }
+            }
+            body @ Body::File { .. } => {
+                writer.flush()?;
+                body.copy_file_to(writer)?;
+            }
+            body => writer.write_all(body.as_ref())?,
+        }
 
         return Ok(());
     }
 
-    fn get_content_type(file_path: &Path) -> Option<&'static str> {
-        return file_path
-            .extension()
-            .and_then(|extension| extension.to_str())
-            .and_then(|extension| match extension {
-                "html" => Some("text/html"),
-                "ico" => Some("x-icon"),
-                "jpg" | "jpeg" => Some("image/jpg"),
-                "gif" => Some("image/gif"),
-                "png" => Some("image/png"),
-                _ => None,
-            });
+    /// `1xx` and `204 No Content` responses end with their headers, so they send no body or framing headers
+    fn forbids_body(&self) -> bool {
+        return self.status.is_informational() || self.status == StatusCode::NO_CONTENT;
+    }
+    /// Picks how the end of a [Body::Stream] is marked, see [Response::is_close_delimited]
+    fn set_framing_headers(&mut self) {
+        if self.forbids_body() {
+            self.headers.remove(HeaderName::CONTENT_LENGTH);
+            self.headers.remove(HeaderName::TRANSFER_ENCODING);
+            return;
+        }
+        if !self.body.is_stream() {
+            return;
+        }
+
+        self.headers.remove(HeaderName::CONTENT_LENGTH);
+        if self.is_close_delimited() {
+            self.set_header(HeaderName::CONNECTION, "close");
+        } else {
+            self.set_header(HeaderName::TRANSFER_ENCODING, "chunked");
+            if !self.trailers.is_empty() {
+                let names = self
+                    .trailers
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<_>>();
+                self.set_header(HeaderName::TRAILER, names.join(", "));
+            }
+        }
     }
 }
 impl Display for Response {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}\r\n", self.start_line().on_blue())?;
-        write!(f, "{}\r\n", self.headers().on_blue())?;
+        write!(f, "{}\r\n", self.headers.to_string().on_blue())?;
         write!(
             f,
             "\r\n{}\r\n",
-            if self.body().len() > 1000 {
+            if self.body().is_stream() {
+                "streamed body".into()
+            } else if self.body().is_file() {
+                "file body".into()
+            } else if self.body().len() > Some(1000) {
                 "body removed for brevity".into()
             } else {
                 String::from_utf8_lossy(self.body.as_ref())
@@ -82,96 +207,114 @@ impl Display for Response {
         return Ok(());
     }
 }
-impl TryFrom<Request> for Response {
-    type Error = Report;
-    fn try_from(value: Request) -> Result<Self, Self::Error> {
-        let path = if value.path() == "/" {
-            "/index.html"
-        } else {
-            value.path()
-        };
-
-        let file_path = PathBuf::from(format!("{}{}", SERVE_DIR, path));
-
-        let mut file_data = Vec::new();
-        let bytes_read =
-            File::open(&file_path).and_then(|mut file| file.read_to_end(&mut file_data));
-
-        let (body, status) = match bytes_read {
-            Ok(_) => (Body::from(file_data), StatusCode::OK),
-            Err(io_error) => {
-                println!(
-                    "in Response::TryFrom<Request> could not read {} because {}\nsetting body to empty",
-                    file_path.display(),
-                    io_error
-                );
-                (Body::from(Vec::new()), StatusCode::NOT_FOUND)
-            }
-        };
-
-        let mut headers = HashMap::new();
-        headers
-            .entry("Content-Length".to_string())
-            .or_insert(body.len().to_string());
-        if let Some(content_type) = Response::get_content_type(&file_path) {
-            headers
-                .entry("Content-Type".to_string())
-                .or_insert(content_type.to_owned());
-
-            if content_type != "text/html" {
-                headers
-                    .entry("Content-Encoding".to_string())
-                    .or_insert("gzip".to_string());
-                // headers
-                // .entry("Accept-Ranges".to_string())
-                // .or_insert("bytes".to_string());
-            }
-        }
-
-        return Ok(Response {
-            body,
-            status,
-            headers,
-            version: value.version(),
-        });
-    }
-}
-
 #[cfg(test)]
 mod test {
-
-    use crate::{handle_connection, split_stream};
-
     use super::*;
+    use crate::{
+        config::Config, handle_connection, split_stream, static_files::StaticFiles,
+        test_util::TempDir,
+    };
+
     use std::{
-        io::Write,
+        io::{Read, Write},
         net::{TcpListener, TcpStream},
         thread,
     };
 
     #[test]
-    fn test_gz_encoded_body() {
-        const LOCALHOST: &str = "localhost:8000";
-
-        let server_task = thread::spawn(|| {
-            let stream = TcpListener::bind(LOCALHOST).and_then(|listener| {
-                listener
-                    .incoming()
-                    .next()
-                    .expect("incoming().next() is never none")
-            });
+    fn test_stream_body_framing() {
+        let chunks = || {
+            Body::from_chunks(vec![
+                Ok(b"hello ".to_vec()),
+                Ok(Vec::new()),
+                Ok(b"world".to_vec()),
+            ])
+        };
+
+        let mut response = Response::empty(StatusCode::OK);
+        response.set_body(chunks());
+        response.set_trailer("Checksum", "abc");
+        let mut sent = Vec::new();
+        response.send(&mut sent).unwrap();
+        let sent = String::from_utf8(sent).unwrap();
+        assert!(sent.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(!sent.contains("Content-Length"));
+        assert!(sent.ends_with("\r\n\r\n6\r\nhello \r\n5\r\nworld\r\n0\r\nChecksum: abc\r\n\r\n"));
+
+        let mut response = Response::empty(StatusCode::OK);
+        response.version = Version::Http1_0;
+        response.set_body(chunks());
+        assert!(response.is_close_delimited());
+        let mut sent = Vec::new();
+        response.send(&mut sent).unwrap();
+        let sent = String::from_utf8(sent).unwrap();
+        assert!(sent.contains("Connection: close\r\n"));
+        assert!(sent.ends_with("\r\n\r\nhello world"));
+    }
+
+    #[test]
+    fn test_no_content_has_no_framing() {
+        let response = Response::empty(StatusCode::NO_CONTENT);
+        assert!(!response.headers().contains(HeaderName::CONTENT_LENGTH));
+
+        let mut response = Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Body::from_chunks(vec![Ok(b"ignored".to_vec())]));
+        let mut sent = Vec::new();
+        response.send(&mut sent).unwrap();
+        let sent = String::from_utf8(sent).unwrap();
+        assert!(!sent.contains("Content-Length"));
+        assert!(!sent.contains("Transfer-Encoding"));
+        assert!(sent.ends_with("\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_serves_static_file() {
+        let listener = TcpListener::bind("localhost:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server_task = thread::spawn(move || {
+            let stream = listener
+                .incoming()
+                .next()
+                .expect("incoming().next() is never none");
             let split_stream = split_stream(stream).unwrap();
-            handle_connection(split_stream).unwrap();
+            handle_connection(
+                split_stream,
+                &Config::default(),
+                &StaticFiles::new("./web").unwrap(),
+            )
+            .unwrap();
         });
 
-        let client_task = thread::spawn(|| {
-            let mut stream = TcpStream::connect(LOCALHOST).unwrap();
-            stream
-                .write(b"GET /assets/duck_hat.jpg HTTP/1.1\r\n")
-                .unwrap();
-        });
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .write_all(b"GET /assets/duck_hat.jpg HTTP/1.1\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
 
         server_task.join().unwrap();
-        client_task.join().unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 200"));
+        let file_data = std::fs::read("./web/assets/duck_hat.jpg").unwrap();
+        assert!(response.ends_with(&file_data));
+    }
+
+    #[test]
+    fn test_file_body_is_copied() {
+        let temp_dir = TempDir::new("body");
+        let path = temp_dir.path().join("body.txt");
+        std::fs::write(&path, "skip this, send this").unwrap();
+        let file = std::fs::File::open(&path).unwrap();
+
+        let mut response = Response::builder().body(Body::File {
+            file,
+            offset: 11,
+            len: 9,
+        });
+        assert_eq!(response.headers().content_length(), Some(9));
+        let mut sent = Vec::new();
+        response.send(&mut sent).unwrap();
+        assert!(sent.ends_with(b"\r\n\r\nsend this"));
     }
 }
@@ -0,0 +1,66 @@
+use crate::my_http::{Body, HeaderMap, HeaderName, HeaderValue, Response, StatusCode, Version};
+
+/// Assembles a [Response] one part at a time, started with [Response::builder].
+///
+/// # Example
+/// ```rust
+/// # use scratchserver::my_http::{Response, StatusCode};
+/// let response = Response::builder()
+///     .status(StatusCode::CREATED)
+///     .header("Location", "/users/7")
+///     .body("created");
+/// assert_eq!(response.status(), StatusCode::CREATED);
+/// ```
+#[derive(Debug)]
+pub struct ResponseBuilder {
+    version: Version,
+    status: StatusCode,
+    headers: HeaderMap,
+    trailers: HeaderMap,
+}
+impl Default for ResponseBuilder {
+    fn default() -> Self {
+        return ResponseBuilder {
+            version: Version::Http1_1,
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            trailers: HeaderMap::new(),
+        };
+    }
+}
+impl ResponseBuilder {
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.status = status;
+        return self;
+    }
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = version;
+        return self;
+    }
+    /// Adds a header, a name given more than once is sent once per value
+    pub fn header(mut self, name: impl Into<HeaderName>, value: impl Into<HeaderValue>) -> Self {
+        self.headers.append(name, value);
+        return self;
+    }
+    /// Adds a trailer field, only sent with a [Body::Stream]
+    pub fn trailer(mut self, name: impl Into<HeaderName>, value: impl Into<HeaderValue>) -> Self {
+        self.trailers.append(name, value);
+        return self;
+    }
+    /// Finishes the response with `body`, setting `Content-Length` to match it
+    pub fn body(self, body: impl Into<Body>) -> Response {
+        let mut response = Response {
+            version: self.version,
+            headers: self.headers,
+            body: Body::from(Vec::new()),
+            status: self.status,
+            trailers: self.trailers,
+        };
+        response.set_body(body.into());
+        return response;
+    }
+    /// Finishes the response without a body
+    pub fn empty(self) -> Response {
+        return self.body(Vec::new());
+    }
+}
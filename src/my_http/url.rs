@@ -54,7 +54,7 @@ impl FromStr for Url {
         let host_port = host_path_parts.next().ok_or(eyre!("Missing host:port"))?;
         let path = host_path_parts
             .next()
-            .unwrap_or(&"")
+            .unwrap_or("")
             .parse()
             .expect("Infallible error");
 
@@ -81,6 +81,7 @@ impl FromStr for Url {
 }
 
 /// [W3Schools url encode](https://www.w3schools.com/tags/ref_urlencode.ASP)
+#[allow(dead_code)]
 pub fn safe_character(character: char) -> Option<&'static str> {
     return match character {
         ' ' => Some("%20"),
@@ -178,7 +179,7 @@ pub fn safe_character(character: char) -> Option<&'static str> {
         '|' => Some("%7C"),
         '}' => Some("%7D"),
         '~' => Some("%7E"),
-        ' ' => Some("%7F"),
+        '\u{7F}' => Some("%7F"),
         '€' => Some("%E2%82%AC"),
         '' => Some("%81"),
         '‚' => Some("%E2%80%9A"),
@@ -211,7 +212,7 @@ pub fn safe_character(character: char) -> Option<&'static str> {
         '' => Some("%9D"),
         'ž' => Some("%C5%BE"),
         'Ÿ' => Some("%C5%B8"),
-        ' ' => Some("%C2%A0"),
+        '\u{A0}' => Some("%C2%A0"),
         '¡' => Some("%C2%A1"),
         '¢' => Some("%C2%A2"),
         '£' => Some("%C2%A3"),
@@ -306,11 +307,59 @@ pub fn safe_character(character: char) -> Option<&'static str> {
         'ü' => Some("%C3%BC"),
         'ý' => Some("%C3%BD"),
         'þ' => Some("%C3%BE"),
-        'ÿ' => Some(r##"%C3%BF"#;"##),
+        'ÿ' => Some("%C3%BF"),
         _ => None,
     };
 }
 
+/// Decodes the `%XX` escapes in `s`, the inverse of [safe_character].
+/// Fails on a malformed escape or when the decoded bytes are not utf-8
+pub fn percent_decode(s: &str) -> Result<String, Report> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] != b'%' {
+            decoded.push(bytes[index]);
+            index += 1;
+            continue;
+        }
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .ok_or(eyre!(
+                "in percent_decode {} has a malformed escape at {}",
+                s,
+                index
+            ))?;
+        let hex = std::str::from_utf8(hex).expect("ascii hex digits are utf-8");
+        decoded.push(u8::from_str_radix(hex, 16).expect("two hex digits fit in a u8"));
+        index += 3;
+    }
+
+    return String::from_utf8(decoded).map_err(|utf8_error| {
+        eyre!(
+            "in percent_decode {} does not decode to utf-8\n{}",
+            s,
+            utf8_error
+        )
+    });
+}
+
+#[test]
+fn test_percent_decode() {
+    assert_eq!(
+        percent_decode("/ducks%20and%2Fgeese").unwrap(),
+        "/ducks and/geese"
+    );
+    assert_eq!(percent_decode("/%2e%2E/secret").unwrap(), "/../secret");
+    assert_eq!(percent_decode("/d%C3%BCck").unwrap(), "/d\u{FC}ck");
+    assert!(percent_decode("/100%").is_err());
+    assert!(percent_decode("/%+1").is_err());
+    assert!(percent_decode("/%FF").is_err());
+}
+
 #[test]
 fn parse_url() {
     let mut output = String::new();
@@ -435,7 +484,7 @@ fn parse_url() {
         "https://prodgame10.alliances.commandandconquer.ro/12/index.aspx",
     ];
 
-    URLS.into_iter().for_each(|url| {
+    URLS.iter().for_each(|url| {
         output.push_str(format!("{}\n{:?}\n\n", url, url.parse::<Url>().unwrap()).as_str())
     });
 
@@ -0,0 +1,268 @@
+use crate::{
+    handler::Handler,
+    my_http::{percent_decode, HeaderName, Method, Request, Response, StatusCode},
+};
+
+use color_eyre::Report;
+
+/// Dispatches requests to handlers by [Method] and path pattern.
+///
+/// A pattern segment starting with `:` captures one segment and `*` captures the rest of the path,
+/// both can be read back percent-decoded with [Request::param], and a capture with a malformed escape gets a `400 Bad Request`.
+/// A path that matches no pattern goes to the fallback, or gets a `404 Not Found`.
+/// A path that matches but not for the request method gets a `405 Method Not Allowed` with an `Allow` header.
+///
+/// # Example
+/// ```rust
+/// # use scratchserver::{my_http::Response, router::Router};
+/// let router = Router::new()
+///     .get("/users/:id", |request| {
+///         Ok(Response::text(format!("user {}", request.param("id").unwrap_or_default())))
+///     })
+///     .get("/assets/*rest", |request| Ok(Response::text(request.param("rest").unwrap_or_default())));
+/// ```
+#[derive(Default)]
+pub struct Router {
+    routes: Vec<Route>,
+    fallback: Option<Box<dyn Handler>>,
+}
+impl Router {
+    pub fn new() -> Self {
+        return Router::default();
+    }
+    /// Sends `method` requests for paths matching `pattern` to `handler`. Earlier routes win when several match
+    pub fn route<F>(mut self, method: Method, pattern: &str, handler: F) -> Self
+    where
+        F: Fn(&mut Request) -> Result<Response, Report> + Send + Sync + 'static,
+    {
+        self.routes.push(Route {
+            method,
+            pattern: Pattern::parse(pattern),
+            handler: Box::new(handler),
+        });
+        return self;
+    }
+    /// A `GET` route, which also answers `HEAD`
+    pub fn get<F>(self, pattern: &str, handler: F) -> Self
+    where
+        F: Fn(&mut Request) -> Result<Response, Report> + Send + Sync + 'static,
+    {
+        return self.route(Method::Get, pattern, handler);
+    }
+    pub fn post<F>(self, pattern: &str, handler: F) -> Self
+    where
+        F: Fn(&mut Request) -> Result<Response, Report> + Send + Sync + 'static,
+    {
+        return self.route(Method::Post, pattern, handler);
+    }
+    pub fn put<F>(self, pattern: &str, handler: F) -> Self
+    where
+        F: Fn(&mut Request) -> Result<Response, Report> + Send + Sync + 'static,
+    {
+        return self.route(Method::Put, pattern, handler);
+    }
+    pub fn delete<F>(self, pattern: &str, handler: F) -> Self
+    where
+        F: Fn(&mut Request) -> Result<Response, Report> + Send + Sync + 'static,
+    {
+        return self.route(Method::Delete, pattern, handler);
+    }
+    /// Answers every request whose path matches no route, instead of a `404 Not Found`.
+    /// Usually a whole other [Handler] like [crate::static_files::StaticFiles]
+    pub fn fallback(mut self, handler: impl Handler + 'static) -> Self {
+        self.fallback = Some(Box::new(handler));
+        return self;
+    }
+}
+impl Handler for Router {
+    fn handle(&self, request: &mut Request) -> Result<Response, Report> {
+        let path = request.path().split('?').next().unwrap_or_default();
+        let segments = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+
+        let mut allowed = Vec::new();
+        let mut matched = None;
+        for route in self.routes.iter() {
+            let params = match route.pattern.matches(&segments) {
+                Some(params) => params,
+                None => continue,
+            };
+            if route.answers(request.method()) {
+                matched = Some((route, params));
+                break;
+            }
+            allowed.push(route.method);
+        }
+
+        if let Some((route, params)) = matched {
+            let params = params
+                .into_iter()
+                .map(|(name, value)| Ok((name, percent_decode(&value)?)))
+                .collect::<Result<Vec<_>, Report>>();
+            let params = match params {
+                Ok(params) => params,
+                Err(_) => return Ok(Response::empty(StatusCode::BAD_REQUEST)),
+            };
+            request.set_params(params);
+            return route.handler.handle(request);
+        }
+
+        if !allowed.is_empty() {
+            if allowed.contains(&Method::Get) {
+                allowed.push(Method::Head);
+            }
+            let allow = allowed
+                .iter()
+                .map(Method::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Ok(Response::builder()
+                .status(StatusCode::METHOD_NOT_ALLOWED)
+                .header(HeaderName::ALLOW, allow)
+                .empty());
+        }
+
+        return match &self.fallback {
+            Some(fallback) => fallback.handle(request),
+            None => Ok(Response::empty(StatusCode::NOT_FOUND)),
+        };
+    }
+}
+
+struct Route {
+    method: Method,
+    pattern: Pattern,
+    handler: Box<dyn Handler>,
+}
+impl Route {
+    fn answers(&self, method: Method) -> bool {
+        return self.method == method || (self.method == Method::Get && method == Method::Head);
+    }
+}
+
+enum Segment {
+    Literal(String),
+    Param(String),
+    Rest(String),
+}
+
+struct Pattern {
+    segments: Vec<Segment>,
+}
+impl Pattern {
+    fn parse(pattern: &str) -> Self {
+        let segments = pattern
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| {
+                if let Some(name) = segment.strip_prefix(':') {
+                    Segment::Param(name.to_string())
+                } else if let Some(name) = segment.strip_prefix('*') {
+                    Segment::Rest(name.to_string())
+                } else {
+                    Segment::Literal(segment.to_string())
+                }
+            })
+            .collect();
+
+        return Pattern { segments };
+    }
+    /// The captured parameters when `path` matches
+    fn matches(&self, path: &[&str]) -> Option<Vec<(String, String)>> {
+        let mut params = Vec::new();
+
+        for (index, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Rest(name) => {
+                    params.push((name.clone(), path.get(index..)?.join("/")));
+                    return Some(params);
+                }
+                Segment::Param(name) => params.push((name.clone(), path.get(index)?.to_string())),
+                Segment::Literal(literal) => {
+                    if path.get(index) != Some(&literal.as_str()) {
+                        return None;
+                    }
+                }
+            }
+        }
+
+        return if path.len() == self.segments.len() {
+            Some(params)
+        } else {
+            None
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn router() -> Router {
+        return Router::new()
+            .get("/users/:id", |request| {
+                Ok(Response::text(format!(
+                    "get {}",
+                    request.param("id").unwrap()
+                )))
+            })
+            .delete("/users/:id", |request| {
+                Ok(Response::text(format!(
+                    "delete {}",
+                    request.param("id").unwrap()
+                )))
+            })
+            .get("/assets/*rest", |request| {
+                Ok(Response::text(request.param("rest").unwrap().to_string()))
+            });
+    }
+
+    fn handle(router: &Router, raw_request: &str) -> Response {
+        let mut request = raw_request.parse().unwrap();
+        return router.handle(&mut request).unwrap();
+    }
+
+    #[test]
+    fn test_params_are_captured() {
+        let router = router();
+
+        let response = handle(&router, "GET /users/7?verbose HTTP/1.1\r\n\r\n");
+        assert_eq!(response.body().as_ref(), b"get 7");
+
+        let response = handle(&router, "DELETE /users/7 HTTP/1.1\r\n\r\n");
+        assert_eq!(response.body().as_ref(), b"delete 7");
+
+        let response = handle(&router, "GET /assets/img/duck.gif HTTP/1.1\r\n\r\n");
+        assert_eq!(response.body().as_ref(), b"img/duck.gif");
+
+        let response = handle(&router, "GET /users/J%C3%BCrgen%20K HTTP/1.1\r\n\r\n");
+        assert_eq!(response.body().as_ref(), "get Jürgen K".as_bytes());
+
+        let response = handle(&router, "GET /assets/a%20b/c.txt HTTP/1.1\r\n\r\n");
+        assert_eq!(response.body().as_ref(), b"a b/c.txt");
+
+        let response = handle(&router, "GET /users/%zz HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_unmatched_requests() {
+        let router = router();
+
+        let response = handle(&router, "GET /users/7/posts HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = handle(&router, "POST /users/7 HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(
+            response.headers().get(HeaderName::ALLOW).unwrap().as_str(),
+            "GET, DELETE, HEAD"
+        );
+
+        let router = router.fallback(|_: &mut Request| Ok(Response::text("fallback")));
+        let response = handle(&router, "GET /missing HTTP/1.1\r\n\r\n");
+        assert_eq!(response.body().as_ref(), b"fallback");
+    }
+}
@@ -0,0 +1,1157 @@
+use crate::{
+    compression::Compression,
+    handler::Handler,
+    my_http::{
+        check_preconditions, percent_decode, AcceptEncoding, Body, ContentCoding, HeaderName,
+        HttpDate, Method, MimeTypes, RangeRequest, Request, Response, StatusCode,
+    },
+};
+
+mod autoindex;
+
+use self::autoindex::autoindex;
+
+use color_eyre::{eyre::eyre, Report};
+use std::{
+    borrow::Cow,
+    collections::hash_map::DefaultHasher,
+    ffi::OsString,
+    fs::{self, File, Metadata},
+    hash::Hasher,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A [Handler] that answers requests with the file at their path under the root given to [StaticFiles::new].
+/// Other directories can be [StaticFiles::mount]ed at url prefixes, the longest matching prefix picks the root.
+/// Files are streamed from disk as a [Body::File] rather than read into memory.
+///
+/// When the client accepts it, a precompressed sibling like `ducks.gif.gz` or `ducks.gif.br`
+/// is sent in place of the file, see [precompress].
+/// `GET` requests with a `Range` header get just those bytes in a `206 Partial Content`,
+/// several ranges are sent as `multipart/byteranges`.
+///
+/// The `Content-Type` comes from the file extension through [MimeTypes], files with an unknown extension
+/// have their first bytes sniffed instead.
+/// Every file is sent with an `ETag` and a `Last-Modified` so clients can revalidate it with
+/// `If-None-Match` or `If-Modified-Since` and get a `304 Not Modified` back, see [check_preconditions]
+///
+/// A directory is served through the first of its [StaticFiles::index_files] that exists. Without one it gets
+/// a `404 Not Found`, or a listing when [StaticFiles::autoindex] is on. A directory requested without a trailing
+/// slash is redirected to the path with one, so relative links in its index resolve against the directory.
+///
+/// `GET` and `HEAD` are answered with the file, `OPTIONS` with the `Allow`ed methods
+/// and any other method with a `405 Method Not Allowed`.
+///
+/// Request paths are percent-decoded and their `.` and `..` segments resolved before they touch the file system.
+/// A path that climbs out of the root gets a `403 Forbidden`, and so does a symlink the [SymlinkPolicy] rejects
+#[derive(Debug, Clone)]
+pub struct StaticFiles {
+    /// `(url prefix, root)` pairs with the longest prefix first, the root given to [StaticFiles::new] has an empty prefix
+    roots: Vec<(String, PathBuf)>,
+    etag_source: ETagSource,
+    mime_types: MimeTypes,
+    symlinks: SymlinkPolicy,
+    index_files: Vec<Cow<'static, str>>,
+    autoindex: bool,
+    show_hidden: bool,
+}
+impl StaticFiles {
+    /// The file names looked for in a directory, in order, when [StaticFiles::index_files] is not set
+    pub const DEFAULT_INDEX_FILES: &'static [&'static str] = &["index.html", "index.htm"];
+
+    /// Serves the files under `root`, which has to be a readable directory
+    pub fn new(root: impl Into<PathBuf>) -> Result<Self, Report> {
+        return Ok(StaticFiles {
+            roots: vec![(String::new(), readable_root(root.into())?)],
+            etag_source: ETagSource::default(),
+            mime_types: MimeTypes::default(),
+            symlinks: SymlinkPolicy::default(),
+            index_files: StaticFiles::DEFAULT_INDEX_FILES
+                .iter()
+                .map(|&index_file| Cow::from(index_file))
+                .collect(),
+            autoindex: false,
+            show_hidden: false,
+        });
+    }
+    /// Serves the files under `root` at `prefix`, so with a prefix of `/assets` the request for
+    /// `/assets/ducks.gif` gets `root/ducks.gif`. Mounting at a prefix again replaces that root
+    pub fn mount(mut self, prefix: &str, root: impl Into<PathBuf>) -> Result<Self, Report> {
+        // kept without a trailing slash, so `/` becomes the empty prefix of the root from [StaticFiles::new]
+        let prefix = match prefix.trim_matches('/') {
+            "" => String::new(),
+            prefix => format!("/{}", prefix),
+        };
+        let root = readable_root(root.into())?;
+
+        self.roots.retain(|(mounted, _)| *mounted != prefix);
+        self.roots.push((prefix, root));
+        self.roots
+            .sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+        return Ok(self);
+    }
+
+    /// Sets the file names a directory is served through, the first one that exists is used
+    pub fn index_files<I>(mut self, index_files: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Cow<'static, str>>,
+    {
+        self.index_files = index_files.into_iter().map(Into::into).collect();
+        return self;
+    }
+    /// Lists the contents of directories without an index file, as html or as json when the `Accept` header
+    /// prefers it. The listing can be sorted with `?sort=name|size|modified&order=asc|desc`
+    pub fn autoindex(mut self, autoindex: bool) -> Self {
+        self.autoindex = autoindex;
+        return self;
+    }
+    /// Includes names starting with `.` in directory listings
+    pub fn show_hidden(mut self, show_hidden: bool) -> Self {
+        self.show_hidden = show_hidden;
+        return self;
+    }
+    /// Sets which symlinks under the root may be followed
+    pub fn symlinks(mut self, symlinks: SymlinkPolicy) -> Self {
+        self.symlinks = symlinks;
+        return self;
+    }
+    /// Serves files ending in `extension` as `media_type`, in place of the built in [MimeTypes]
+    pub fn mime_type(mut self, extension: &str, media_type: impl Into<String>) -> Self {
+        self.mime_types.insert(extension, media_type);
+        return self;
+    }
+    /// Sets what the `ETag` of each file is computed from
+    pub fn etag_source(mut self, etag_source: ETagSource) -> Self {
+        self.etag_source = etag_source;
+        return self;
+    }
+}
+impl Handler for StaticFiles {
+    fn handle(&self, request: &mut Request) -> Result<Response, Report> {
+        return self.serve(request);
+    }
+}
+
+/// Which symlinks [StaticFiles] follows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymlinkPolicy {
+    /// Follow every symlink, wherever it points
+    Follow,
+    /// Follow symlinks that resolve to somewhere under the root
+    #[default]
+    WithinRoot,
+    /// Never serve a path that goes through a symlink
+    Deny,
+}
+impl FromStr for SymlinkPolicy {
+    type Err = Report;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match s {
+            "follow" => Ok(SymlinkPolicy::Follow),
+            "within-root" => Ok(SymlinkPolicy::WithinRoot),
+            "deny" => Ok(SymlinkPolicy::Deny),
+            invalid => Err(eyre!(
+                "{} is not a valid symlink policy, expected follow, within-root or deny",
+                invalid
+            )),
+        };
+    }
+}
+
+/// What [StaticFiles] computes an `ETag` from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ETagSource {
+    /// The size and modification time of the file, which costs no reads
+    #[default]
+    Metadata,
+    /// A hash of the file contents, which stays the same when a file is rewritten unchanged
+    /// but reads the whole file on every request
+    ContentHash,
+}
+impl ETagSource {
+    fn etag(&self, file: &File, metadata: &Metadata) -> Result<String, io::Error> {
+        return match self {
+            ETagSource::Metadata => {
+                let modified = metadata
+                    .modified()?
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                Ok(format!(
+                    "\"{:x}-{:x}\"",
+                    metadata.len(),
+                    modified.as_nanos()
+                ))
+            }
+            ETagSource::ContentHash => {
+                let mut hasher = DefaultHasher::new();
+                let mut file = file;
+                let mut chunk = vec![0; Body::CHUNK_SIZE];
+                loop {
+                    match file.read(&mut chunk)? {
+                        0 => break,
+                        bytes_read => hasher.write(&chunk[..bytes_read]),
+                    }
+                }
+                Ok(format!("\"{:x}-{:016x}\"", metadata.len(), hasher.finish()))
+            }
+        };
+    }
+}
+
+/// The codings a precompressed sibling can have, preferred in this order
+const PRECOMPRESSED: &[ContentCoding] = &[
+    ContentCoding::Brotli,
+    ContentCoding::Zstd,
+    ContentCoding::Gzip,
+];
+
+impl StaticFiles {
+    /// The methods static files answer to, sent in `Allow`
+    const ALLOW: &str = "GET, HEAD, OPTIONS";
+
+    fn serve(&self, request: &Request) -> Result<Response, Report> {
+        match request.method() {
+            Method::Get | Method::Head => {}
+            // also answers the asterisk-form `OPTIONS *` about the server as a whole
+            Method::Options => {
+                return Ok(Response::builder()
+                    .status(StatusCode::NO_CONTENT)
+                    .header(HeaderName::ALLOW, StaticFiles::ALLOW)
+                    .empty())
+            }
+            _ => {
+                return Ok(Response::builder()
+                    .status(StatusCode::METHOD_NOT_ALLOWED)
+                    .header(HeaderName::ALLOW, StaticFiles::ALLOW)
+                    .empty())
+            }
+        }
+
+        let segments = match normalize(request.path()) {
+            Ok(segments) => segments,
+            Err(status) => return Ok(Response::empty(status)),
+        };
+        let Some((root, rest)) = self.mounted(&segments) else {
+            return Ok(Response::empty(StatusCode::NOT_FOUND));
+        };
+        let mut file_path = match self.resolve(root, rest) {
+            Ok(file_path) => file_path,
+            Err(status) => return Ok(Response::empty(status)),
+        };
+        if file_path.is_dir() {
+            let (path, query) = match request.path().split_once('?') {
+                Some((path, query)) => (path, Some(query)),
+                None => (request.path(), None),
+            };
+            if !path.ends_with('/') {
+                // built from the normalised segments, the raw path could make `//host` or `/\host` which clients read as another host
+                let location = match query {
+                    Some(query) => format!("{}?{}", directory_url(&segments), query),
+                    None => directory_url(&segments),
+                };
+                return Ok(Response::redirect(StatusCode::MOVED_PERMANENTLY, &location));
+            }
+
+            let index = self
+                .index_files
+                .iter()
+                .map(|index_file| file_path.join(index_file.as_ref()))
+                .find(|index| index.is_file());
+            match index {
+                Some(index) => {
+                    if let Err(status) = self.check_symlinks(root, &index) {
+                        return Ok(Response::empty(status));
+                    }
+                    file_path = index;
+                }
+                None if self.autoindex => {
+                    let base = directory_url(&segments);
+                    return autoindex(self, request, root, &file_path, &base);
+                }
+                None => return Ok(Response::empty(StatusCode::NOT_FOUND)),
+            }
+        }
+
+        let siblings = self.precompressed_siblings(root, &file_path);
+        let (read_path, coding) = choose_sibling(request, &siblings).unwrap_or((&file_path, None));
+
+        let opened = File::open(read_path).and_then(|file| {
+            let metadata = file.metadata()?;
+            if !metadata.is_file() {
+                return Err(io::Error::new(io::ErrorKind::NotFound, "not a file"));
+            }
+            let etag = self.etag_source.etag(&file, &metadata)?;
+            let last_modified = HttpDate::from(metadata.modified()?);
+            return Ok((Body::from_file(file)?, Some((etag, last_modified))));
+        });
+
+        let ((body, validators), status) = match opened {
+            Ok(opened) => (opened, StatusCode::OK),
+            Err(io_error) => {
+                println!(
+                    "in StaticFiles::handle could not read {} because {}\nsetting body to empty",
+                    file_path.display(),
+                    io_error
+                );
+                ((Body::from(Vec::new()), None), StatusCode::NOT_FOUND)
+            }
+        };
+
+        let mut response = Response::builder().status(status);
+        if status == StatusCode::OK {
+            let media_type = match self.mime_types.for_path(&file_path) {
+                Some(media_type) => media_type,
+                None => sniff_media_type(&file_path)?,
+            };
+            response = response.header(
+                HeaderName::CONTENT_TYPE,
+                MimeTypes::content_type(media_type),
+            );
+            if let Some(coding) = coding {
+                response = response.header(HeaderName::CONTENT_ENCODING, coding.as_str());
+            }
+            if !siblings.is_empty() {
+                response = response.header(HeaderName::VARY, HeaderName::ACCEPT_ENCODING.as_str());
+            }
+            response = response.header(HeaderName::ACCEPT_RANGES, "bytes");
+        }
+        if let Some((etag, last_modified)) = validators {
+            response = response
+                .header(HeaderName::ETAG, etag)
+                .header(HeaderName::LAST_MODIFIED, last_modified.to_string());
+        }
+        let response = response.body(body);
+
+        if status == StatusCode::OK {
+            if let Some(precondition_response) = check_preconditions(request, &response) {
+                return Ok(precondition_response);
+            }
+        }
+
+        if status == StatusCode::OK && request.method() == Method::Get {
+            if let Some(range) = request.header(HeaderName::RANGE) {
+                if if_range_matches(request, &response) {
+                    return partial(response, range);
+                }
+            }
+        }
+
+        return Ok(response);
+    }
+
+    /// The root mounted at the longest prefix of the [normalize]d `segments`, and the segments after that prefix.
+    /// A prefix only matches whole segments, `/assets` is no prefix of `/assetsx`
+    fn mounted<'a>(&self, segments: &'a [String]) -> Option<(&Path, &'a [String])> {
+        return self.roots.iter().find_map(|(prefix, root)| {
+            let mut rest = segments;
+            for prefix_segment in prefix.split('/').filter(|segment| !segment.is_empty()) {
+                let (first, after) = rest.split_first()?;
+                if first != prefix_segment {
+                    return None;
+                }
+                rest = after;
+            }
+            return Some((root.as_path(), rest));
+        });
+    }
+    /// Maps [normalize]d `segments` onto a file under `root`, checked against the [SymlinkPolicy]
+    fn resolve(&self, root: &Path, segments: &[String]) -> Result<PathBuf, StatusCode> {
+        let mut file_path = root.to_path_buf();
+        file_path.extend(segments);
+        self.check_symlinks(root, &file_path)?;
+        return Ok(file_path);
+    }
+    /// Whether `path`, which is lexically under `root`, may be served under the [SymlinkPolicy]
+    fn check_symlinks(&self, root: &Path, path: &Path) -> Result<(), StatusCode> {
+        return match self.symlinks {
+            SymlinkPolicy::Follow => Ok(()),
+            SymlinkPolicy::WithinRoot => {
+                let (Ok(root), Ok(path)) = (root.canonicalize(), path.canonicalize()) else {
+                    return Err(StatusCode::NOT_FOUND);
+                };
+                if path.starts_with(root) {
+                    Ok(())
+                } else {
+                    Err(StatusCode::FORBIDDEN)
+                }
+            }
+            SymlinkPolicy::Deny => {
+                let relative = path.strip_prefix(root).unwrap_or(path);
+                let mut current = root.to_path_buf();
+                for component in relative.components() {
+                    current.push(component);
+                    let is_symlink = current
+                        .symlink_metadata()
+                        .is_ok_and(|metadata| metadata.file_type().is_symlink());
+                    if is_symlink {
+                        return Err(StatusCode::FORBIDDEN);
+                    }
+                }
+                Ok(())
+            }
+        };
+    }
+    /// The precompressed files next to `file_path`, only looked for when `file_path` itself exists
+    fn precompressed_siblings(
+        &self,
+        root: &Path,
+        file_path: &Path,
+    ) -> Vec<(ContentCoding, PathBuf)> {
+        if !file_path.is_file() {
+            return Vec::new();
+        }
+        return PRECOMPRESSED
+            .iter()
+            .filter_map(|&coding| {
+                let sibling = sibling_path(file_path, coding)?;
+                let allowed = sibling.is_file() && self.check_symlinks(root, &sibling).is_ok();
+                allowed.then_some((coding, sibling))
+            })
+            .collect();
+    }
+}
+
+/// `root` if it is a directory the server can list, so a bad root fails at startup rather than on every request
+fn readable_root(root: PathBuf) -> Result<PathBuf, Report> {
+    if let Err(io_error) = fs::read_dir(&root) {
+        return Err(eyre!(
+            "in StaticFiles::new {} is not a readable directory\n{}",
+            root.display(),
+            io_error
+        ));
+    }
+    return Ok(root);
+}
+
+/// Whether the `If-Range` validator, if any, still matches the file so the `Range` header applies.
+/// Otherwise the file changed since the client got its first part, and it needs the whole file again
+fn if_range_matches(request: &Request, response: &Response) -> bool {
+    let Some(if_range) = request.header(HeaderName::IF_RANGE) else {
+        return true;
+    };
+    let if_range = if_range.trim();
+
+    // weak entity tags never match here, only a byte for byte identical file may be pieced together
+    if if_range.starts_with("W/") {
+        return false;
+    }
+    let validator = if if_range.starts_with('"') {
+        HeaderName::ETAG
+    } else {
+        HeaderName::LAST_MODIFIED
+    };
+    return response
+        .headers()
+        .get(validator)
+        .is_some_and(|value| value.as_str() == if_range);
+}
+
+/// Splits a request path into its segments: the query is dropped, escapes decoded and `.` and `..` resolved.
+/// Done once, before a mount is picked, so `/web/../x` and `/%77eb/x` are matched as `/x` and `/web/x`
+fn normalize(request_path: &str) -> Result<Vec<String>, StatusCode> {
+    let path = request_path.split(['?', '#']).next().unwrap_or_default();
+    let path = percent_decode(path).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if path.contains('\0') {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => continue,
+            ".." => {
+                if segments.pop().is_none() {
+                    return Err(StatusCode::FORBIDDEN);
+                }
+            }
+            // a backslash would be another separator to windows
+            segment if cfg!(windows) && segment.contains('\\') => {
+                return Err(StatusCode::BAD_REQUEST)
+            }
+            segment => segments.push(segment.to_string()),
+        }
+    }
+    return Ok(segments);
+}
+
+/// Escapes everything but unreserved characters so a file name is one path segment
+fn percent_encode(name: &str) -> String {
+    return name
+        .chars()
+        .map(|character| match character {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '.' | '_' | '~' => character.to_string(),
+            character => character
+                .to_string()
+                .bytes()
+                .map(|byte| format!("%{:02X}", byte))
+                .collect(),
+        })
+        .collect();
+}
+
+/// The path of the directory at the [normalize]d `segments`, percent-encoded and ending in `/`
+fn directory_url(segments: &[String]) -> String {
+    let mut url = String::from("/");
+    for segment in segments {
+        url.push_str(&percent_encode(segment));
+        url.push('/');
+    }
+    return url;
+}
+
+/// Narrows a whole file `response` down to what the `Range` header `range` asks for
+fn partial(mut response: Response, range: &str) -> Result<Response, Report> {
+    let complete_len = response.body().len().unwrap_or_default() as u64;
+
+    let ranges = match RangeRequest::parse(range, complete_len) {
+        RangeRequest::Ignored => return Ok(response),
+        RangeRequest::Unsatisfiable => {
+            return Ok(Response::builder()
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(HeaderName::ACCEPT_RANGES, "bytes")
+                .header(
+                    HeaderName::CONTENT_RANGE,
+                    format!("bytes */{}", complete_len),
+                )
+                .empty());
+        }
+        RangeRequest::Satisfiable(ranges) => ranges,
+    };
+
+    response.set_status(StatusCode::PARTIAL_CONTENT);
+    if let [range] = ranges[..] {
+        let body = response.body().slice(range)?;
+        response.set_header(HeaderName::CONTENT_RANGE, range.content_range(complete_len));
+        response.set_body(body);
+        return Ok(response);
+    }
+
+    let boundary = format!(
+        "{:x}",
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+    );
+    let content_type = response.headers().content_type().map(str::to_string);
+
+    // each part's headers go out ahead of its slice of the file, which is only read while the response is sent
+    let mut parts = Vec::new();
+    for (index, range) in ranges.into_iter().enumerate() {
+        let mut part_head = Vec::new();
+        if index > 0 {
+            part_head.extend_from_slice(b"\r\n");
+        }
+        write!(part_head, "--{}\r\n", boundary)?;
+        if let Some(content_type) = &content_type {
+            write!(
+                part_head,
+                "{}: {}\r\n",
+                HeaderName::CONTENT_TYPE,
+                content_type
+            )?;
+        }
+        write!(
+            part_head,
+            "{}: {}\r\n\r\n",
+            HeaderName::CONTENT_RANGE,
+            range.content_range(complete_len)
+        )?;
+        parts.push(Body::Data(part_head).into_chunks());
+        parts.push(response.body().slice(range)?.into_chunks());
+    }
+    parts.push(Body::String(format!("\r\n--{}--\r\n", boundary)).into_chunks());
+
+    response.set_header(
+        HeaderName::CONTENT_TYPE,
+        format!("multipart/byteranges; boundary={}", boundary),
+    );
+    response.set_body(Body::from_chunks(parts.into_iter().flatten()));
+    return Ok(response);
+}
+
+/// The sibling the request's `Accept-Encoding` prefers, [None] when the plain file should be sent
+fn choose_sibling<'a>(
+    request: &Request,
+    siblings: &'a [(ContentCoding, PathBuf)],
+) -> Option<(&'a PathBuf, Option<ContentCoding>)> {
+    let available = siblings
+        .iter()
+        .map(|(coding, _)| *coding)
+        .chain([ContentCoding::Identity])
+        .collect::<Vec<_>>();
+    let accept_encoding =
+        AcceptEncoding::parse(request.headers().get_list(HeaderName::ACCEPT_ENCODING));
+    let coding = accept_encoding.negotiate(&available)?;
+
+    return siblings
+        .iter()
+        .find(|(sibling_coding, _)| *sibling_coding == coding)
+        .map(|(_, sibling)| (sibling, Some(coding)));
+}
+
+fn sibling_path(file_path: &Path, coding: ContentCoding) -> Option<PathBuf> {
+    let mut sibling = OsString::from(file_path.as_os_str());
+    sibling.push(".");
+    sibling.push(coding.file_extension()?);
+    return Some(PathBuf::from(sibling));
+}
+
+/// Writes a compressed sibling of every file under `root`, for each [ContentCoding] this build supports
+/// that has a [ContentCoding::file_extension].
+///
+/// Media types that [Compression] skips, symlinks and siblings that would not be smaller than the file are left out.
+/// Returns the paths written
+pub fn precompress(root: impl AsRef<Path>) -> Result<Vec<PathBuf>, Report> {
+    let root = root.as_ref();
+    if !root.is_dir() {
+        return Err(eyre!(
+            "in precompress {} is not a directory",
+            root.display()
+        ));
+    }
+
+    let compression = Compression::default();
+    let mime_types = MimeTypes::default();
+    let mut written = Vec::new();
+    let mut directories = vec![root.to_path_buf()];
+
+    while let Some(directory) = directories.pop() {
+        for entry in fs::read_dir(&directory)? {
+            let entry = entry?;
+            let path = entry.path();
+            // the entry's own type, so a symlink back up the tree can not send the walk round in a loop
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                directories.push(path);
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+            let is_sibling = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    PRECOMPRESSED
+                        .iter()
+                        .any(|coding| coding.file_extension() == Some(extension))
+                });
+            let is_skipped = mime_types
+                .for_path(&path)
+                .is_some_and(|media_type| compression.is_skipped(media_type));
+            if is_sibling || is_skipped {
+                continue;
+            }
+
+            let data = fs::read(&path)?;
+            for &coding in PRECOMPRESSED.iter().filter(|coding| coding.is_supported()) {
+                let encoded = coding.encode(&data)?;
+                if encoded.len() >= data.len() {
+                    continue;
+                }
+                if let Some(sibling) = sibling_path(&path, coding) {
+                    fs::write(&sibling, encoded)?;
+                    written.push(sibling);
+                }
+            }
+        }
+    }
+
+    return Ok(written);
+}
+
+/// Guesses the media type of a file without a known extension from its first bytes
+fn sniff_media_type(file_path: &Path) -> Result<&'static str, io::Error> {
+    let mut start = Vec::with_capacity(MimeTypes::SNIFF_LEN);
+    File::open(file_path)?
+        .take(MimeTypes::SNIFF_LEN as u64)
+        .read_to_end(&mut start)?;
+    return Ok(MimeTypes::sniff(&start));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{my_http::Version, test_util::TempDir};
+
+    #[test]
+    fn test_precompressed_siblings_are_served() {
+        let temp_dir = TempDir::new("precompressed");
+        let root = temp_dir.path();
+        fs::write(root.join("index.html"), "<p>quack</p>".repeat(100)).unwrap();
+        fs::write(root.join("duck.gif"), [0x47; 1000]).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root, root.join("loop")).unwrap();
+
+        let written = precompress(root).unwrap();
+        assert!(written.contains(&root.join("index.html.gz")));
+        assert!(!root.join("duck.gif.gz").exists());
+        assert!(!written
+            .iter()
+            .any(|path| path.starts_with(root.join("loop"))));
+
+        let request: Request = "GET / HTTP/1.1\r\nAccept-Encoding: gzip\r\n\r\n"
+            .parse()
+            .unwrap();
+        let response = StaticFiles::new(root).unwrap().serve(&request).unwrap();
+        let headers = response.headers();
+        assert_eq!(
+            headers.get(HeaderName::CONTENT_ENCODING).unwrap().as_str(),
+            "gzip"
+        );
+        assert_eq!(headers.content_type(), Some("text/html; charset=utf-8"));
+        assert_eq!(
+            headers.get(HeaderName::VARY).unwrap().as_str(),
+            "Accept-Encoding"
+        );
+        assert_eq!(
+            response.body().read_bytes().unwrap(),
+            fs::read(root.join("index.html.gz")).unwrap()
+        );
+
+        let request: Request = "GET / HTTP/1.1\r\nAccept-Encoding: identity\r\n\r\n"
+            .parse()
+            .unwrap();
+        let response = StaticFiles::new(root).unwrap().serve(&request).unwrap();
+        assert!(!response.headers().contains(HeaderName::CONTENT_ENCODING));
+        assert_eq!(response.body().len(), Some(1200));
+    }
+
+    #[test]
+    fn test_conditional_get() {
+        let temp_dir = TempDir::new("etag");
+        let root = temp_dir.path();
+        fs::write(root.join("index.html"), "<p>quack</p>").unwrap();
+
+        let get = |static_files: &StaticFiles, headers: &str| {
+            let request: Request = format!("GET / HTTP/1.1\r\n{}\r\n", headers)
+                .parse()
+                .unwrap();
+            return static_files.serve(&request).unwrap();
+        };
+
+        for static_files in [
+            StaticFiles::new(root).unwrap(),
+            StaticFiles::new(root)
+                .unwrap()
+                .etag_source(ETagSource::ContentHash),
+        ] {
+            let response = get(&static_files, "");
+            let etag = response
+                .headers()
+                .get(HeaderName::ETAG)
+                .unwrap()
+                .to_string();
+            let last_modified = response
+                .headers()
+                .get(HeaderName::LAST_MODIFIED)
+                .unwrap()
+                .to_string();
+
+            let response = get(&static_files, &format!("If-None-Match: {}\r\n", etag));
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+            assert_eq!(
+                response.headers().get(HeaderName::ETAG).unwrap().as_str(),
+                etag
+            );
+            assert!(!response.headers().contains(HeaderName::CONTENT_LENGTH));
+
+            let response = get(
+                &static_files,
+                &format!("If-Modified-Since: {}\r\n", last_modified),
+            );
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+            let response = get(&static_files, "If-None-Match: \"other\"\r\n");
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let response = get(&static_files, "If-Match: \"other\"\r\n");
+            assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+
+            let response = get(
+                &static_files,
+                &format!("Range: bytes=0-2\r\nIf-Range: {}\r\n", etag),
+            );
+            assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        }
+    }
+
+    #[test]
+    fn test_content_types() {
+        let temp_dir = TempDir::new("mime");
+        let root = temp_dir.path();
+        fs::write(root.join("app.js"), "console.log('quack')").unwrap();
+        fs::write(root.join("duck.quack"), "quack").unwrap();
+        fs::write(root.join("README"), "# ducks").unwrap();
+        fs::write(root.join("duck"), b"GIF89a\x01\x00\x01\x00").unwrap();
+
+        let static_files = StaticFiles::new(root)
+            .unwrap()
+            .mime_type("quack", "audio/x-quack");
+        let content_type = |path: &str| {
+            let request: Request = format!("GET {} HTTP/1.1\r\n\r\n", path).parse().unwrap();
+            let response = static_files.serve(&request).unwrap();
+            return response.headers().content_type().unwrap().to_string();
+        };
+
+        assert_eq!(content_type("/app.js"), "text/javascript; charset=utf-8");
+        assert_eq!(content_type("/duck.quack"), "audio/x-quack");
+        assert_eq!(content_type("/README"), "text/plain; charset=utf-8");
+        assert_eq!(content_type("/duck"), "image/gif");
+    }
+
+    #[test]
+    fn test_paths_stay_under_the_root() {
+        let temp_dir = TempDir::new("paths");
+        let base = temp_dir.path();
+        let root = base.join("web");
+        fs::create_dir_all(root.join("assets")).unwrap();
+        fs::write(base.join("secret.txt"), "secret").unwrap();
+        fs::write(root.join("assets/duck.txt"), "quack").unwrap();
+
+        let status = |static_files: &StaticFiles, path: &str| {
+            let request: Request = format!("GET {} HTTP/1.1\r\n\r\n", path).parse().unwrap();
+            return static_files.serve(&request).unwrap().status();
+        };
+        let static_files = StaticFiles::new(&root).unwrap();
+
+        assert_eq!(
+            status(&static_files, "/assets/duck.txt?v=2"),
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(&static_files, "/assets/./../assets/duck.txt"),
+            StatusCode::OK
+        );
+        assert_eq!(status(&static_files, "/assets/%64uck.txt"), StatusCode::OK);
+        assert_eq!(
+            status(&static_files, "/../secret.txt"),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status(&static_files, "/assets/../../secret.txt"),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status(&static_files, "/%2e%2e/secret.txt"),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status(&static_files, "/assets/duck.txt%00.html"),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(&static_files, "/assets/%zz"),
+            StatusCode::BAD_REQUEST
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::symlink;
+            symlink(base.join("secret.txt"), root.join("escape.txt")).unwrap();
+            symlink(root.join("assets/duck.txt"), root.join("alias.txt")).unwrap();
+
+            assert_eq!(status(&static_files, "/escape.txt"), StatusCode::FORBIDDEN);
+            assert_eq!(status(&static_files, "/alias.txt"), StatusCode::OK);
+
+            let follow = StaticFiles::new(&root)
+                .unwrap()
+                .symlinks(SymlinkPolicy::Follow);
+            assert_eq!(status(&follow, "/escape.txt"), StatusCode::OK);
+
+            let deny = StaticFiles::new(&root)
+                .unwrap()
+                .symlinks(SymlinkPolicy::Deny);
+            assert_eq!(status(&deny, "/alias.txt"), StatusCode::FORBIDDEN);
+            assert_eq!(status(&deny, "/assets/duck.txt"), StatusCode::OK);
+        }
+    }
+
+    #[test]
+    fn test_range_requests() {
+        let temp_dir = TempDir::new("range");
+        let root = temp_dir.path();
+        fs::write(root.join("alphabet.html"), "abcdefghijklmnopqrstuvwxyz").unwrap();
+
+        let get = |headers: &str| {
+            let request: Request = format!("GET /alphabet.html HTTP/1.1\r\n{}\r\n", headers)
+                .parse()
+                .unwrap();
+            return StaticFiles::new(root).unwrap().serve(&request).unwrap();
+        };
+
+        let response = get("Range: bytes=2-4\r\n");
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            response
+                .headers()
+                .get(HeaderName::CONTENT_RANGE)
+                .unwrap()
+                .as_str(),
+            "bytes 2-4/26"
+        );
+        assert_eq!(response.body().read_bytes().unwrap().as_ref(), b"cde");
+
+        let mut response = get("Range: bytes=0-0, -2\r\n");
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        let content_type = response.headers().content_type().unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/byteranges; boundary=")
+            .unwrap();
+        let expected = format!(
+            "--{0}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Range: bytes 0-0/26\r\n\r\na\r\n\
+             --{0}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Range: bytes 24-25/26\r\n\r\nyz\r\n\
+             --{0}--\r\n",
+            boundary
+        );
+        response.set_version(Version::Http1_0);
+        let mut sent = Vec::new();
+        response.send(&mut sent).unwrap();
+        assert!(String::from_utf8(sent).unwrap().ends_with(&expected));
+
+        let response = get("Range: bytes=26-\r\n");
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(
+            response
+                .headers()
+                .get(HeaderName::CONTENT_RANGE)
+                .unwrap()
+                .as_str(),
+            "bytes */26"
+        );
+
+        let response = get("Range: bytes=2-4\r\nIf-Range: \"stale\"\r\n");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.body().len(), Some(26));
+    }
+
+    #[test]
+    fn test_autoindex() {
+        let temp_dir = TempDir::new("autoindex");
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("assets/nested")).unwrap();
+        fs::write(root.join("assets/big duck.txt"), "quack quack").unwrap();
+        fs::write(root.join("assets/a.txt"), "a").unwrap();
+        fs::write(root.join("assets/.secret"), "secret").unwrap();
+        fs::write(root.join("assets/caf\u{e9}\u{85}.txt"), "").unwrap();
+        let outside = TempDir::new("autoindex-outside");
+        fs::write(outside.path().join("elsewhere.txt"), "elsewhere").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(
+            outside.path().join("elsewhere.txt"),
+            root.join("assets/elsewhere.txt"),
+        )
+        .unwrap();
+
+        let serve = |static_files: &StaticFiles, request: &str| {
+            let request: Request = request.parse().unwrap();
+            let response = static_files.serve(&request).unwrap();
+            let body = String::from_utf8_lossy(response.body().as_ref()).to_string();
+            return (response.status(), body);
+        };
+        let request = "GET /assets/ HTTP/1.1\r\n\r\n";
+
+        assert_eq!(
+            serve(&StaticFiles::new(root).unwrap(), request).0,
+            StatusCode::NOT_FOUND
+        );
+
+        let static_files = StaticFiles::new(root).unwrap().autoindex(true);
+        let (status, html) = serve(&static_files, request);
+        assert_eq!(status, StatusCode::OK);
+        assert!(html.contains("<a href=\"/assets/big%20duck.txt\">big duck.txt</a>"));
+        assert!(html.contains("<a href=\"../\">"));
+        assert!(!html.contains(".secret"));
+        assert!(html.contains("<a href=\"/assets/caf%C3%A9%C2%85.txt\">"));
+        // directories first, then by name
+        let nested = html.find("nested/").unwrap();
+        let a = html.find("a.txt").unwrap();
+        let big_duck = html.find("big duck.txt").unwrap();
+        assert!(nested < a && a < big_duck);
+
+        let (_, html) = serve(
+            &static_files,
+            "GET /assets/?sort=size&order=desc HTTP/1.1\r\n\r\n",
+        );
+        assert!(html.find("big duck.txt").unwrap() < html.find("a.txt").unwrap());
+
+        let (_, json) = serve(
+            &static_files,
+            "GET /assets/ HTTP/1.1\r\nAccept: text/html;q=0.5, application/json\r\n\r\n",
+        );
+        assert!(json.starts_with(
+            "{\"path\":\"/assets/\",\"entries\":[{\"name\":\"nested\",\"type\":\"directory\""
+        ));
+        assert!(json.contains("{\"name\":\"a.txt\",\"type\":\"file\",\"size\":1,\"modified\":\""));
+
+        // the listing links stay on this origin whatever the raw path looked like
+        let (status, html) = serve(&static_files, "GET //evil.com/../assets/ HTTP/1.1\r\n\r\n");
+        assert_eq!(status, StatusCode::OK);
+        assert!(html.contains("<a href=\"/assets/a.txt\">"));
+        assert!(!html.contains("evil.com"));
+
+        let (_, html) = serve(&static_files.clone().show_hidden(true), request);
+        assert!(html.contains(".secret"));
+
+        #[cfg(unix)]
+        {
+            assert!(!html.contains("elsewhere.txt"));
+            let follow = static_files.clone().symlinks(SymlinkPolicy::Follow);
+            assert!(serve(&follow, request).1.contains("elsewhere.txt"));
+        }
+
+        fs::write(root.join("assets/index.html"), "<h1>assets</h1>").unwrap();
+        let request: Request = request.parse().unwrap();
+        let response = static_files.serve(&request).unwrap();
+        assert!(response.body().is_file());
+        assert_eq!(response.body().len(), Some(15));
+    }
+
+    #[test]
+    fn test_directory_index() {
+        let temp_dir = TempDir::new("index");
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("assets/empty")).unwrap();
+        fs::write(root.join("index.html"), "<p>quack</p>").unwrap();
+        fs::write(root.join("assets/index.htm"), "<p>ducks</p>").unwrap();
+        fs::write(root.join("assets/home.html"), "<p>home</p>").unwrap();
+
+        let serve = |static_files: &StaticFiles, path: &str| {
+            let request: Request = format!("GET {} HTTP/1.1\r\n\r\n", path).parse().unwrap();
+            return static_files.serve(&request).unwrap();
+        };
+        let static_files = StaticFiles::new(root).unwrap();
+
+        assert_eq!(serve(&static_files, "/").body().len(), Some(12));
+        assert_eq!(serve(&static_files, "/assets/").body().len(), Some(12));
+        assert_eq!(
+            serve(&static_files, "/assets/empty/").status(),
+            StatusCode::NOT_FOUND
+        );
+
+        let redirect = serve(&static_files, "/assets?v=2");
+        assert_eq!(redirect.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(
+            redirect
+                .headers()
+                .get(HeaderName::LOCATION)
+                .map(|location| location.as_str()),
+            Some("/assets/?v=2")
+        );
+        assert_eq!(
+            serve(&static_files, "/assets/empty").status(),
+            StatusCode::MOVED_PERMANENTLY
+        );
+        assert_eq!(
+            serve(&static_files, "//example.com/..")
+                .headers()
+                .get(HeaderName::LOCATION)
+                .map(|location| location.as_str()),
+            Some("/")
+        );
+        assert_eq!(
+            serve(&static_files, "/\\example.com/..")
+                .headers()
+                .get(HeaderName::LOCATION)
+                .map(|location| location.as_str()),
+            Some("/")
+        );
+        assert_eq!(
+            serve(&static_files, "/./assets/../assets/%65mpty")
+                .headers()
+                .get(HeaderName::LOCATION)
+                .map(|location| location.as_str()),
+            Some("/assets/empty/")
+        );
+
+        let static_files = static_files.index_files(["home.html"]);
+        assert_eq!(serve(&static_files, "/assets/").body().len(), Some(11));
+        assert_eq!(serve(&static_files, "/").status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_methods() {
+        let temp_dir = TempDir::new("methods");
+        let root = temp_dir.path();
+        fs::write(root.join("index.html"), "<p>quack</p>").unwrap();
+
+        let serve = |request: &str| {
+            let request: Request = request.parse().unwrap();
+            return StaticFiles::new(root).unwrap().serve(&request).unwrap();
+        };
+        let allow = |response: &Response| {
+            return response
+                .headers()
+                .get(HeaderName::ALLOW)
+                .map(|allow| allow.as_str().to_string());
+        };
+
+        let head = serve("HEAD /index.html HTTP/1.1\r\n\r\n");
+        assert_eq!(head.status(), StatusCode::OK);
+        assert_eq!(head.headers().content_length(), Some(12));
+
+        for request in [
+            "OPTIONS /index.html HTTP/1.1\r\n\r\n",
+            "OPTIONS * HTTP/1.1\r\n\r\n",
+        ] {
+            let options = serve(request);
+            assert_eq!(options.status(), StatusCode::NO_CONTENT);
+            assert_eq!(allow(&options).as_deref(), Some("GET, HEAD, OPTIONS"));
+            assert!(!options.headers().contains(HeaderName::CONTENT_LENGTH));
+        }
+
+        let delete = serve("DELETE /index.html HTTP/1.1\r\n\r\n");
+        assert_eq!(delete.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(allow(&delete).as_deref(), Some("GET, HEAD, OPTIONS"));
+        assert!(root.join("index.html").exists());
+    }
+
+    #[test]
+    fn test_mounts() {
+        let temp_dir = TempDir::new("mounts");
+        let base = temp_dir.path();
+        let root = base.join("web");
+        let assets = base.join("srv/assets");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&assets).unwrap();
+        fs::write(root.join("index.html"), "<p>quack</p>").unwrap();
+        fs::write(root.join("duck.txt"), "web").unwrap();
+        fs::write(assets.join("duck.txt"), "assets").unwrap();
+
+        assert!(StaticFiles::new(base.join("missing")).is_err());
+        assert!(StaticFiles::new(root.join("index.html")).is_err());
+        assert!(StaticFiles::new(&root)
+            .unwrap()
+            .mount("/assets", base.join("missing"))
+            .is_err());
+
+        let static_files = StaticFiles::new(&root)
+            .unwrap()
+            .mount("/assets/", &assets)
+            .unwrap();
+        let serve = |path: &str| {
+            let request: Request = format!("GET {} HTTP/1.1\r\n\r\n", path).parse().unwrap();
+            return static_files.serve(&request).unwrap();
+        };
+
+        assert_eq!(serve("/duck.txt").body().len(), Some(3));
+        assert_eq!(serve("/assets/duck.txt").body().len(), Some(6));
+        assert_eq!(serve("/assetsduck.txt").status(), StatusCode::NOT_FOUND);
+        assert_eq!(serve("/assets/../duck.txt").body().len(), Some(3));
+        assert_eq!(serve("/assets/../assets/duck.txt").body().len(), Some(6));
+        assert_eq!(serve("/%61ssets/duck.txt").body().len(), Some(6));
+        assert_eq!(serve("/../duck.txt").status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            serve("/assets")
+                .headers()
+                .get(HeaderName::LOCATION)
+                .map(|location| location.as_str()),
+            Some("/assets/")
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use super::{percent_encode, StaticFiles};
+use crate::my_http::{
+    escape_html, escape_json, percent_decode, preferred_media_type, HttpDate, Request, Response,
+};
+
+use color_eyre::Report;
+use std::{fmt::Write, fs, path::Path};
+
+/// One file or directory in a listing
+struct Entry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<HttpDate>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortBy {
+    Name,
+    Size,
+    Modified,
+}
+
+/// Lists the entries of `directory` as html, or as json when the request's `Accept` prefers it.
+///
+/// Directories come first, then entries are sorted by the `sort` query parameter (`name`, `size` or `modified`)
+/// in the `order` one (`asc` or `desc`). Names starting with `.` are left out unless [StaticFiles::show_hidden] is set,
+/// and so is every entry that the [super::SymlinkPolicy] would not serve from `root`.
+/// Links are made under `base`, the url of the directory built from the normalised request path
+pub(super) fn autoindex(
+    static_files: &StaticFiles,
+    request: &Request,
+    root: &Path,
+    directory: &Path,
+    base: &str,
+) -> Result<Response, Report> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if name.starts_with('.') && !static_files.show_hidden {
+            continue;
+        }
+        if static_files.check_symlinks(root, &entry.path()).is_err() {
+            continue;
+        }
+        // follows symlinks so a link to a directory lists as one
+        let Ok(metadata) = fs::metadata(entry.path()) else {
+            continue;
+        };
+        entries.push(Entry {
+            name,
+            is_dir: metadata.is_dir(),
+            size: metadata.len(),
+            modified: metadata.modified().ok().map(HttpDate::from),
+        });
+    }
+
+    let query = request
+        .path()
+        .split_once('?')
+        .map_or("", |(_, query)| query);
+    let mut sort_by = SortBy::Name;
+    let mut descending = false;
+    for (key, value) in query.split('&').filter_map(|pair| pair.split_once('=')) {
+        match (key, value) {
+            ("sort", "name") => sort_by = SortBy::Name,
+            ("sort", "size") => sort_by = SortBy::Size,
+            ("sort", "modified") => sort_by = SortBy::Modified,
+            ("order", "desc") => descending = true,
+            ("order", "asc") => descending = false,
+            _ => {}
+        }
+    }
+
+    entries.sort_by(|a, b| {
+        let ordering = match sort_by {
+            SortBy::Name => a.name.cmp(&b.name),
+            SortBy::Size => a.size.cmp(&b.size).then_with(|| a.name.cmp(&b.name)),
+            SortBy::Modified => a
+                .modified
+                .cmp(&b.modified)
+                .then_with(|| a.name.cmp(&b.name)),
+        };
+        let ordering = if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        return b.is_dir.cmp(&a.is_dir).then(ordering);
+    });
+
+    let prefers_json = preferred_media_type(request, &["text/html", "application/json"])
+        == Some("application/json");
+    return Ok(if prefers_json {
+        Response::json(json_listing(base, &entries))
+    } else {
+        Response::html(html_listing(base, &entries, sort_by, descending))
+    });
+}
+
+fn html_listing(base: &str, entries: &[Entry], sort_by: SortBy, descending: bool) -> String {
+    let title = escape_html(&percent_decoded_or_raw(base));
+    let header = |label: &str, column: SortBy, key: &str| {
+        let order = if column == sort_by && !descending {
+            "desc"
+        } else {
+            "asc"
+        };
+        return format!(
+            "<th><a href=\"?sort={}&amp;order={}\">{}</a></th>",
+            key, order, label
+        );
+    };
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Index of {0}</title></head>\n<body>\n<h1>Index of {0}</h1>\n<table>\n<tr>{1}{2}{3}</tr>\n",
+        title,
+        header("Name", SortBy::Name, "name"),
+        header("Size", SortBy::Size, "size"),
+        header("Last modified", SortBy::Modified, "modified"),
+    );
+    if base != "/" {
+        html.push_str("<tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+    }
+    for entry in entries {
+        let slash = if entry.is_dir { "/" } else { "" };
+        let size = if entry.is_dir {
+            "-".to_string()
+        } else {
+            entry.size.to_string()
+        };
+        let modified = entry
+            .modified
+            .map(|modified| modified.to_string())
+            .unwrap_or_default();
+        let _ = writeln!(
+            html,
+            "<tr><td><a href=\"{}{}{}\">{}{}</a></td><td>{}</td><td>{}</td></tr>",
+            escape_html(base),
+            percent_encode(&entry.name),
+            slash,
+            escape_html(&entry.name),
+            slash,
+            size,
+            modified
+        );
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+
+    return html;
+}
+
+fn json_listing(base: &str, entries: &[Entry]) -> String {
+    let entries = entries
+        .iter()
+        .map(|entry| {
+            let modified = entry
+                .modified
+                .map(|modified| format!("\"{}\"", modified))
+                .unwrap_or("null".to_string());
+            let slash = if entry.is_dir { "/" } else { "" };
+            format!(
+                "{{\"name\":\"{}\",\"type\":\"{}\",\"size\":{},\"modified\":{},\"href\":\"{}\"}}",
+                escape_json(&entry.name),
+                if entry.is_dir { "directory" } else { "file" },
+                entry.size,
+                modified,
+                escape_json(&format!("{}{}{}", base, percent_encode(&entry.name), slash)),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+
+    return format!(
+        "{{\"path\":\"{}\",\"entries\":[{}]}}",
+        escape_json(&percent_decoded_or_raw(base)),
+        entries
+    );
+}
+
+fn percent_decoded_or_raw(path: &str) -> String {
+    return percent_decode(path).unwrap_or(path.to_string());
+}
@@ -0,0 +1,27 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A directory under the system temp directory for one test, removed again when dropped so a failing test does not leave it behind
+pub struct TempDir {
+    path: PathBuf,
+}
+impl TempDir {
+    /// Creates an empty `scratchserver-{name}-{process id}` directory, clearing out anything an earlier run left there
+    pub fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("scratchserver-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        return TempDir { path };
+    }
+    pub fn path(&self) -> &Path {
+        return &self.path;
+    }
+}
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,175 @@
+use color_eyre::{eyre::eyre, Report};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+/// A fixed number of worker threads that share one handler and pull items off a bounded queue.
+///
+/// When the queue is full [ThreadPool::execute] blocks the caller and [ThreadPool::try_execute] hands the item back,
+/// so a burst of connections never spawns more threads than `worker_count`.
+pub struct ThreadPool<T> {
+    workers: Vec<JoinHandle<()>>,
+    sender: Option<SyncSender<T>>,
+}
+impl<T: Send + 'static> ThreadPool<T> {
+    pub const DEFAULT_WORKER_COUNT: usize = 8;
+    pub const DEFAULT_QUEUE_DEPTH: usize = 64;
+
+    /// Spawns `worker_count` threads that each call `handler` on the items they receive.
+    /// At most `queue_depth` items wait in the queue while every worker is busy.
+    pub fn new<F>(worker_count: usize, queue_depth: usize, handler: F) -> Result<Self, Report>
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        if worker_count == 0 {
+            return Err(eyre!("in ThreadPool::new worker_count must be at least 1"));
+        }
+
+        let (sender, receiver) = mpsc::sync_channel(queue_depth);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let handler = Arc::new(handler);
+
+        let mut workers = Vec::with_capacity(worker_count);
+        for id in 0..worker_count {
+            let receiver = Arc::clone(&receiver);
+            let handler = Arc::clone(&handler);
+            let worker = thread::Builder::new()
+                .name(format!("worker-{}", id))
+                .spawn(move || ThreadPool::work(&receiver, handler.as_ref()))
+                .map_err(|io_error| {
+                    eyre!(
+                        "in ThreadPool::new could not spawn worker {}\n{}",
+                        id,
+                        io_error
+                    )
+                })?;
+            workers.push(worker);
+        }
+
+        return Ok(ThreadPool {
+            workers,
+            sender: Some(sender),
+        });
+    }
+    /// Queues `item` for the next free worker, blocking while the queue is full
+    pub fn execute(&self, item: T) -> Result<(), Report> {
+        return self
+            .sender()
+            .send(item)
+            .map_err(|_| eyre!("in ThreadPool::execute every worker has stopped"));
+    }
+    /// Queues `item` for the next free worker, or gives it back if the queue is full
+    pub fn try_execute(&self, item: T) -> Result<(), T> {
+        return match self.sender().try_send(item) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(item)) | Err(TrySendError::Disconnected(item)) => Err(item),
+        };
+    }
+    pub fn worker_count(&self) -> usize {
+        return self.workers.len();
+    }
+
+    fn sender(&self) -> &SyncSender<T> {
+        return self
+            .sender
+            .as_ref()
+            .expect("sender is only taken when the pool is dropped");
+    }
+    /// The loop each worker runs until the pool is dropped.
+    /// A panic in `handler` only loses the item it was handling, the worker goes on with the next one
+    fn work(receiver: &Mutex<Receiver<T>>, handler: &(dyn Fn(T) + Send + Sync)) {
+        loop {
+            // the lock is released at the end of this statement so other workers can receive while this one works
+            let item = match receiver.lock() {
+                Ok(receiver) => receiver.recv(),
+                Err(_) => return,
+            };
+            match item {
+                Ok(item) => {
+                    if panic::catch_unwind(AssertUnwindSafe(|| handler(item))).is_err() {
+                        eprintln!(
+                            "{} panicked on an item",
+                            thread::current().name().unwrap_or("worker")
+                        );
+                    }
+                }
+                Err(_) => return,
+            }
+        }
+    }
+}
+impl<T> Drop for ThreadPool<T> {
+    /// Closes the queue and waits for every worker to finish the items already queued
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::{atomic::AtomicUsize, atomic::Ordering, Barrier};
+
+    #[test]
+    fn test_every_item_is_handled() {
+        let handled = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&handled);
+        let pool = ThreadPool::new(4, 2, move |n: usize| {
+            counter.fetch_add(n, Ordering::SeqCst);
+        })
+        .unwrap();
+
+        for n in 1..=100 {
+            pool.execute(n).unwrap();
+        }
+        drop(pool);
+
+        assert_eq!(handled.load(Ordering::SeqCst), 5050);
+    }
+
+    #[test]
+    fn test_workers_survive_panics() {
+        let handled = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&handled);
+        let pool = ThreadPool::new(1, 1, move |n: usize| {
+            if n > 5 {
+                panic!("item {} panicked", n);
+            }
+            counter.fetch_add(1, Ordering::SeqCst);
+        })
+        .unwrap();
+
+        for n in 1..=10 {
+            pool.execute(n).unwrap();
+        }
+        drop(pool);
+
+        assert_eq!(handled.load(Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn test_full_queue_gives_item_back() {
+        let barrier = Arc::new(Barrier::new(2));
+        let worker_barrier = Arc::clone(&barrier);
+        let pool = ThreadPool::new(1, 1, move |_: usize| {
+            worker_barrier.wait();
+        })
+        .unwrap();
+
+        // the first item blocks the only worker, the second fills the queue
+        pool.execute(1).unwrap();
+        while pool.try_execute(2).is_err() {}
+        assert_eq!(pool.try_execute(3), Err(3));
+
+        barrier.wait();
+        barrier.wait();
+    }
+}